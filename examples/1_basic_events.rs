@@ -1,5 +1,6 @@
 //! One thread runs continuously to read stdin. When a line is read, it publishes an event.
 //! Another thread runs only when this event is triggered and prints the result to the console.
+//! The application exits when stdin is closed.
 
 use std::io::prelude::*;
 
@@ -22,6 +23,7 @@ impl MainLoop for ReadStdin {
                 line: line.unwrap(),
             });
         }
+        let _ = self.exit_with(0, "stdin was closed");
     }
 }
 
@@ -31,6 +33,6 @@ impl PrintStdoutEventHandlers for PrintStdout {
     }
 }
 
-fn main() {
-    pel_main();
+fn main() -> pel::PelExit {
+    pel_main()
 }
@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// An event which could not be handled, with the reason and the loop which should have handled
+/// it.
+///
+/// Dead letters are sent to the loops declared with `on dead letter => handler`, or logged if there
+/// are none.
+#[derive(Clone, Debug)]
+pub struct PelDeadLetter<E> {
+    pub event: E,
+    pub reason: String,
+    pub receiver: &'static str,
+}
+
+impl<E> PelDeadLetter<E> {
+    pub fn new(event: E, reason: impl Into<String>, receiver: &'static str) -> Self {
+        PelDeadLetter {
+            event,
+            reason: reason.into(),
+            receiver,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for PelDeadLetter<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} was not handled by {}: {}",
+            self.event, self.receiver, self.reason
+        )
+    }
+}
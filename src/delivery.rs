@@ -0,0 +1,13 @@
+/// How the events published by a loop reach the loops which are subscribed to them.
+///
+/// Selected with the optional `delivery:` section of create\_event\_loops!.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelDelivery {
+    /// Every event is sent to the main event loop, which logs it then forwards it to the
+    /// subscribed loops. This is the default.
+    #[default]
+    Hub,
+    /// Every loop holds the senders of the other loops and sends its events to the subscribed
+    /// loops itself. The main event loop only logs the events and handles the exit.
+    Direct,
+}
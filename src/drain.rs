@@ -0,0 +1,32 @@
+/// How many pending events an active loop handles before each main\_loop iteration.
+///
+/// Selected per active loop with `drains all`, `drains one` or `drains N` in
+/// create\_event\_loops!.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelDrain {
+    /// Every event pending when process\_events is called. This is the default.
+    #[default]
+    All,
+    /// At most the given number of events.
+    UpTo(usize),
+    /// A single event.
+    One,
+}
+
+impl PelDrain {
+    /// Returns how many events to handle when n\_pending\_events are waiting.
+    ///
+    /// Always at least one, so that a disconnection of the queue is noticed.
+    pub const fn n_max_events(self, n_pending_events: usize) -> usize {
+        let n_max_events = match self {
+            PelDrain::All => n_pending_events,
+            PelDrain::UpTo(n_max_events) => n_max_events,
+            PelDrain::One => 1,
+        };
+        if n_max_events == 0 {
+            1
+        } else {
+            n_max_events
+        }
+    }
+}
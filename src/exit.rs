@@ -0,0 +1,42 @@
+use std::fmt;
+use std::process::{ExitCode, Termination};
+
+/// How the application exited: returned by pel\_main.
+///
+/// Can be returned from main, the code then becomes the exit status of the process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PelExit {
+    pub code: i32,
+    pub reason: String,
+}
+
+impl PelExit {
+    pub fn new(code: i32, reason: impl Into<String>) -> Self {
+        PelExit {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    /// True if the code is 0.
+    pub fn is_success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+impl fmt::Display for PelExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "exit code {}: {}", self.code, self.reason)
+    }
+}
+
+impl Termination for PelExit {
+    /// Codes which do not fit in an exit status, from 1 to 255 on most platforms, become
+    /// ExitCode::FAILURE, so that a failure is never reported as a success.
+    fn report(self) -> ExitCode {
+        match self.code {
+            0..=255 => ExitCode::from(self.code as u8),
+            _ => ExitCode::FAILURE,
+        }
+    }
+}
@@ -10,6 +10,11 @@
 //!     active_loop.main_loop();
 //! }
 //! ```
+//! By default, process\_events() handles every pending event before returning. An active loop
+//! can instead handle one event or up to N events per main\_loop() iteration:
+//! ```ignore
+//! active loops: TimerPrinter {} subscribes to (InputReceived) drains 16
+//! ```
 //!
 //! Reactive loops run only when they receive events. The process\_events() function is blocking:
 //! the thread goes to sleep when there are no events to process.
@@ -25,7 +30,7 @@
 //!     match event {
 //!         PelAllEvents::EventA(event_a) => self.on_event_a(event_a),
 //!         PelAllEvents::EventB(event_b) => self.on_event_b(event_b),
-//!         _ => panic!("Unhandled event"),
+//!         event => self.send_dead_letter(event, "the loop has no handler for this event"),
 //!     }
 //! }
 //! ```
@@ -53,20 +58,380 @@
 //!     }
 //! }
 //! ```
+//!
+//! By default, published events go through the main event loop, which logs them then sends them
+//! to the subscribed loops. With `delivery: Direct`, each loop sends its events to the subscribed
+//! loops itself, and the main event loop is only kept for logging and exit handling:
+//! ```ignore
+//! pel::create_event_loops!(
+//!     events: InputReceived { line: String }
+//!     active loops: ReadStdin {} publishes (InputReceived)
+//!     reactive loops: PrintStdout {} subscribes to (InputReceived)
+//!     delivery: Direct
+//! );
+//! ```
+//!
+//! Events are shared between the loops subscribed to them and are never copied to be routed.
+//! By default, handlers receive the event by value: it is moved to the last loop holding it
+//! and cloned for the others. A loop can instead receive the event by ref or by arc:
+//! ```ignore
+//! reactive loops: PrintStdout {} subscribes to (InputReceived by ref, TimerReset by arc)
+//! ```
+//! ```ignore
+//! impl PrintStdoutEventHandlers for PrintStdout {
+//!     fn on_input_received(&mut self, event: &InputReceived) {}
+//!     fn on_timer_reset(&mut self, event: Arc<TimerReset>) {}
+//! }
+//! ```
+//!
+//! A subscription can be given a filter. Events it rejects are not sent to the loop at all:
+//! ```ignore
+//! reactive loops: PrintStdout {} subscribes to (InputReceived where |event| !event.line.is_empty())
+//! ```
+//!
+//! A loop can be declared with several instances, each running in its own thread. The events
+//! sent to the loop are spread over the instances (HashByKey by default, RoundRobin or
+//! LeastQueued), and PelAllEventLoops holds a Vec of instances:
+//! ```ignore
+//! reactive loops: ImageResizer {} subscribes to (ImageReceived) instances 8 balanced by LeastQueued
+//! ```
+//!
+//! With HashByKey, events whose fields are marked with #[key] always go to the same instance for
+//! the same key values, so they are handled in the order they were published. Events without
+//! keys are spread round-robin. RoundRobin and LeastQueued ignore the keys:
+//! ```ignore
+//! events: Trade { #[key] account: u64, amount: u64 }
+//! reactive loops: TradeBook {} subscribes to (Trade) instances 4 balanced by HashByKey
+//! ```
+//!
+//! Events can be given a priority (0 by default). The main event loop and the event loops
+//! handle the events with a higher priority first, and events of the same priority in the order
+//! they were sent:
+//! ```ignore
+//! events: Shutdown {} priority 10, InputReceived { line: String }
+//! ```
+//!
+//! Event queues are unbounded by default. A loop can bound its queue, to at least 1 event, and
+//! choose what happens when it is full (Block the sender, DropNewest, DropOldest or return an
+//! Error from try\_publish functions):
+//! ```ignore
+//! reactive loops: PrintStdout {} subscribes to (InputReceived) capacity 1024 when full DropOldest
+//! ```
+//!
+//! Block and Error apply to the publisher in both delivery modes: through the main event loop,
+//! the publisher first waits for room in the queues of the subscribed loops, or gets the error
+//! back with the event, which is then not sent to any loop. Each refusal is a dead letter.
+//! A loop publishing an event it subscribes to does not wait for its own queue. Loops blocking
+//! on each other's full queues deadlock: a cycle of subscriptions needs another policy. The main
+//! event loop itself never waits for a queue nor gets refused by it: when publishers race for
+//! the last places of a queue, it may briefly hold more events than its capacity.
+//!
+//! An event can be declared with a response type (between parentheses if it is not a single
+//! token). Its handlers return the response, and the loops which publish it get a request
+//! function returning a PelResponse to wait for the response of the first handler:
+//! ```ignore
+//! events: GetBalance { account: u64 } -> u64
+//!
+//! fn on_get_balance(&mut self, event: GetBalance) -> u64 { self.balance }
+//!
+//! let balance = self.request_get_balance(GetBalance::new(7))
+//!     .wait_timeout(Duration::from_secs(1));
+//! ```
+//!
+//! Loops are subscribed to the events of their `subscribes to` clause at start. They can stop
+//! and resume receiving each of them at runtime, for every instance of the loop at once:
+//! ```ignore
+//! self.unsubscribe_input_received();
+//! self.subscribe_input_received();
+//! ```
+//!
+//! Events can be published later, after a delay or at a given instant. The main event loop holds
+//! them until then and publishes them like any publisher: it waits for room in the full queues
+//! whose policy is Block, and a full queue whose policy is Error refuses the event, which becomes a
+//! dead letter. The returned handle cancels the publication:
+//! ```ignore
+//! let timer = self.publish_timer_reset_after(Duration::from_millis(500), TimerReset::new(1, 0));
+//! timer.cancel();
+//! ```
+//!
+//! Reactive loops can tick periodically. They still sleep until the next event or tick, and the
+//! tick handler of their EventHandlers trait is called at fixed intervals: a late tick does not
+//! delay the next ones. Periods are written with a unit among ns, us, ms, s, m and h:
+//! ```ignore
+//! reactive loops: PrintStdout {} every 1s => on_tick
+//!
+//! impl PrintStdoutEventHandlers for PrintStdout {
+//!     fn on_tick(&mut self) {}
+//! }
+//! ```
+//!
+//! Reactive loops can also be told when they got no event for a while, to expire sessions or flush
+//! buffers. on\_idle is called again each time the timeout elapses, until an event arrives:
+//! ```ignore
+//! reactive loops: Session {} subscribes to (InputReceived) idle after 5s
+//!
+//! impl SessionEventHandlers for Session {
+//!     fn on_input_received(&mut self, event: InputReceived) {}
+//!     fn on_idle(&mut self, idle_for: Duration) {}
+//! }
+//! ```
+//!
+//! Calling exit from any loop shuts the application down: every loop handles the events already
+//! in its queue, then its on\_shutdown hook is called (from MainLoop for active loops, from their
+//! EventHandlers trait for reactive loops) and its thread ends. pel\_main then joins the threads
+//! and returns:
+//! ```ignore
+//! impl PrintStdoutEventHandlers for PrintStdout {
+//!     fn on_shutdown(&mut self) {
+//!         self.output.flush().unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! exit\_with also gives the exit code and reason returned by pel\_main, which main can return as
+//! the exit status of the process. The reason is logged by the main event loop:
+//! ```ignore
+//! self.exit_with(2, "the configuration file is invalid");
+//!
+//! fn main() -> pel::PelExit {
+//!     pel_main()
+//! }
+//! ```
+//!
+//! Symmetrically, the on\_start hook is called by the thread of each loop before it handles any
+//! event. A loop can wait for other loops to be started first, and a startup barrier holds every
+//! event back until all loops are started. Dependencies must not form a cycle: creating loops
+//! which start after each other panics:
+//! ```ignore
+//! reactive loops: Cache {} subscribes to (InputReceived) starts after (Database), Database {}
+//! startup: Barrier
+//! ```
+//!
+//! The thread of each loop catches the panics of its hooks, handlers and main\_loop. By default, a
+//! panic stops the loop and makes the application exit with code 101. A loop can instead be
+//! restarted (its fields are reset to their initial values and on\_start is called again, unless
+//! the loop is shutting down), restarted a limited number of times per time window before
+//! escalating, or ignore its panics. Crashes and restarts are logged:
+//! ```ignore
+//! reactive loops: PrintStdout {} subscribes to (InputReceived) on panic Restart(3 per 1m)
+//! ```
+//!
+//! Events which cannot be handled (the queue of a subscriber refused them or a loop has no handler
+//! for them) become dead letters, holding the event, the reason and the loop which should have
+//! handled it. They are logged, unless loops are declared as dead-letter sinks, in which case they
+//! are sent to these loops. Events no loop is subscribed to are not dead letters: they are dropped
+//! and only logged at debug level.
+//! ```ignore
+//! reactive loops: Auditor {} on dead letter => on_dead_letter
+//!
+//! impl AuditorEventHandlers for Auditor {
+//!     fn on_dead_letter(&mut self, dead_letter: pel::PelDeadLetter<PelAllEvents>) {
+//!         eprintln!("{}", dead_letter);
+//!     }
+//! }
+//! ```
+//!
+//! The thread of each loop is named after the loop. Its name, stack size, CPU affinity and
+//! scheduling priority (a nice value or a SCHED\_FIFO priority) can be set per loop, the last two
+//! on Linux only:
+//! ```ignore
+//! active loops: MarketFeed {} publishes (Quote)
+//!     thread named "feed" stack 262144 pinned to (2, 3) priority Fifo(50)
+//! ```
+//!
+//! With many small reactive loops, one thread per loop wastes memory and context switches.
+//! pel\_launch\_event\_loops\_in\_workers can replace pel\_launch\_event\_loops\_in\_threads to
+//! schedule the reactive loops onto a fixed number of worker threads. Each loop still handles its
+//! events one at a time, and active loops keep their own thread. A handler which blocks holds its
+//! worker: a handler publishing to a full `when full Block` queue waits for its consumer, in Direct
+//! and Hub delivery alike, and the consumer never runs if every worker is held that way. Such
+//! systems need more workers than blocked publishers, or another backpressure policy:
+//! ```ignore
+//! let (main_event_loop, all_event_loops) = pel_create_event_loops();
+//! let threads = pel_launch_event_loops_in_workers(all_event_loops, 4);
+//! let exit = pel_run_main_loop_indefinitely(main_event_loop);
+//! threads.join(pel::PEL_SHUTDOWN_GRACE_PERIOD);
+//! ```
+//!
+//! With the `async` feature, `async loops:` declares loops whose handlers can await. They are
+//! routed like the other loops and run as tasks of a tokio runtime: the current one when the loops
+//! are launched, or the one given to pel\_launch\_event\_loops\_on\_runtime. Handler futures must
+//! be Send. Async loops only take `publishes`, `subscribes to` and `capacity`: `every`, `idle
+//! after`, `instances`, `starts after`, `on panic`, `on dead letter` and the thread settings are
+//! not supported, and a panic always makes the application exit with code 101:
+//! ```ignore
+//! async loops: Fetcher {client: Client = Client::new()} subscribes to (UrlReceived)
+//!
+//! impl FetcherEventHandlers for Fetcher {
+//!     async fn on_url_received(&mut self, event: UrlReceived) {
+//!         let body = self.client.get(&event.url).await;
+//!     }
+//! }
+//! ```
+//!
+//! pel\_main and pel\_run\_main\_loop\_indefinitely take over the calling thread. pel\_spawn
+//! runs the main event loop in a thread of its own instead, and returns a handle to publish
+//! events, shut the loops down and wait for them:
+//! ```ignore
+//! let system = pel_spawn();
+//! system.publish(InputReceived::new(String::from("hello")))?;
+//! system.shutdown()?;
+//! let exit = system.join();
+//! ```
+//!
+//! Threads which are not loops, like callbacks of C libraries, publish through a PelPublisher,
+//! given by pel\_create\_event\_loops\_with\_publisher or by the handle of pel\_spawn. It has a
+//! publish\_ function for every event, can be cloned and sent to other threads, and its events
+//! are logged as published by external:
+//! ```ignore
+//! let publisher = system.publisher();
+//! std::thread::spawn(move || publisher.publish_input_received(InputReceived::new(line)));
+//! ```
+//!
+//! Everything create\_event\_loops! generates has a fixed name. To have several systems in one
+//! crate, each can be generated in its own private module with `system: Name`. The fields of its
+//! loops and its pel\_ functions are then visible from the module of the invocation, and each
+//! system is created and launched on its own:
+//! ```ignore
+//! pel::create_event_loops!(
+//!     system: Gateway
+//!     events: Request { path: String }
+//!     active loops: Listener {} publishes (Request)
+//!     reactive loops: Router {} subscribes to (Request)
+//! );
+//!
+//! impl gateway::RouterEventHandlers for gateway::Router {
+//!     fn on_request(&mut self, event: gateway::Request) {}
+//! }
+//!
+//! let (main_event_loop, all_event_loops) = gateway::pel_create_event_loops();
+//! ```
+//!
+//! A process has a single logger: the first system which initializes logging sets it. The `log
+//! file` of the other systems is not applied, and a warning saying so is logged to the first one.
+//!
+//! The pel\_ functions are private by default. A visibility before `system:` or `events:`, for
+//! instance `pub system: Gateway`, applies to them and to the fields of the loops, so that a
+//! library can export its systems to be launched by a binary or by the tests of another crate.
+
+mod dead_letter;
+mod delivery;
+mod drain;
+mod exit;
+mod pool;
+mod queue;
+mod request;
+mod scheduler;
+mod startup;
+mod subscription;
+mod supervision;
+mod threads;
+mod timer;
+
+pub use dead_letter::PelDeadLetter;
+pub use delivery::PelDelivery;
+pub use drain::PelDrain;
+pub use exit::PelExit;
+pub use pool::{PelBalance, PelPoolSender};
+pub use queue::{pel_channel, PelBackpressure, PelReceiver, PelSendError, PelSender};
+pub use request::{PelEvent, PelResponder, PelResponse, PelResponseError};
+// Used by the code generated for pel_launch_event_loops_in_workers
+#[doc(hidden)]
+pub use scheduler::{PelScheduler, PelTask, PelTaskStatus, PelWaker, PEL_START_POLL_PERIOD};
+pub use startup::{pel_check_start_order, PelLatch, PelStartup};
+pub use subscription::PelSubscription;
+pub use supervision::{pel_panic_message, PelSupervision, PelSupervisor, PelSupervisorAction};
+pub use threads::{
+    PelEndSignal, PelThreadConfig, PelThreadPriority, PelThreads, PEL_SHUTDOWN_GRACE_PERIOD,
+};
+pub use timer::{pel_parse_duration, PelIdleTimer, PelTicker, PelTimerHandle, PelTimers};
+
+// Used by the code generated for async loops
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub use tokio as __pel_tokio;
 
 #[macro_export]
 macro_rules! create_event_loops {
-    (events: $($event_name: ident { $($event_field: ident : $event_field_type: ty),* }),*
+    // Generates the system in its own module, so that several systems can coexist. Its entry
+    // points are visible from the module of the invocation.
+    (system: $system: ident $($system_definition: tt)*) => {
+        ::paste::paste! {
+            mod [<$system:snake>] {
+                #[allow(unused_imports)]
+                use super::*;
+
+                $crate::create_event_loops!(@pel_internal pub(super) $($system_definition)*);
+            }
+        }
+    };
+
+    // Like above, the module and its entry points having the given visibility, for instance to
+    // export the system from a library
+    ($visibility: vis system: $system: ident $($system_definition: tt)*) => {
+        ::paste::paste! {
+            $visibility mod [<$system:snake>] {
+                #[allow(unused_imports)]
+                use super::*;
+
+                $crate::create_event_loops!(@pel_internal $visibility $($system_definition)*);
+            }
+        }
+    };
+
+    ($visibility: vis events: $($system_definition: tt)*) => {
+        $crate::create_event_loops!(@pel_internal $visibility events: $($system_definition)*);
+    };
+
+    // The visibility applies to the fields of the loops and to the functions creating and
+    // launching them
+    (@pel_internal $visibility: vis
+     events: $($event_name: ident {
+                   $($(#[$event_field_attribute: ident])? $event_field: ident : $event_field_type: ty),*
+               }
+               $(-> $event_response: tt)?
+               $(priority $event_priority: literal)?),*
 
      $(active loops: $($active_loop_name: ident
             { $($field_active: ident : $type_active: ty = $init_field_active: expr),* }
             $(publishes ( $($event_to_publish_active: ident),* ))?
-            $(subscribes to ( $($event_to_react_to_active: ident),*))?),*)?
+            $(subscribes to ( $($event_to_react_to_active: ident $(by $active_passing: ident)?
+                                $(where $active_filter: expr)?),*))?
+            $(on dead letter => $active_dead_letter_handler: ident)?
+            $(starts after ( $($active_dependency: ident),* ))?
+            $(capacity $active_capacity: literal $(when full $active_backpressure: ident)?)?
+            $(drains $active_drain: tt)?
+            $(instances $active_instances: literal $(balanced by $active_balance: ident)?)?
+            $(on panic $active_supervision: ident
+                $(($active_max_restarts: literal per $active_restart_window: tt))?)?
+            $(thread $(named $active_thread_name: literal)? $(stack $active_stack_size: literal)?
+                $(pinned to ( $($active_cpu: literal),* ))?
+                $(priority $active_priority: ident ( $active_priority_value: literal ))?)?),*)?
 
      $(reactive loops: $($reactive_loop_name: ident
             { $($field_reactive: ident : $type_reactive: ty = $init_field_reactive: expr),* }
             $(publishes ( $($event_to_publish_reactive: ident),*))?
-            $(subscribes to ( $($event_to_react_to_reactive: ident),*))?),*)?
+            $(subscribes to ( $($event_to_react_to_reactive: ident $(by $reactive_passing: ident)?
+                                $(where $reactive_filter: expr)?),*))?
+            $(on dead letter => $reactive_dead_letter_handler: ident)?
+            $(starts after ( $($reactive_dependency: ident),* ))?
+            $(every $reactive_period: tt => $reactive_tick: ident)?
+            $(idle after $reactive_idle_timeout: tt)?
+            $(capacity $reactive_capacity: literal $(when full $reactive_backpressure: ident)?)?
+            $(instances $reactive_instances: literal $(balanced by $reactive_balance: ident)?)?
+            $(on panic $reactive_supervision: ident
+                $(($reactive_max_restarts: literal per $reactive_restart_window: tt))?)?
+            $(thread $(named $reactive_thread_name: literal)? $(stack $reactive_stack_size: literal)?
+                $(pinned to ( $($reactive_cpu: literal),* ))?
+                $(priority $reactive_priority: ident ( $reactive_priority_value: literal ))?)?),*)?
+     $(async loops: $($async_loop_name: ident
+            { $($field_async: ident : $type_async: ty = $init_field_async: expr),* }
+            $(publishes ( $($event_to_publish_async: ident),*))?
+            $(subscribes to ( $($event_to_react_to_async: ident $(by $async_passing: ident)?
+                                $(where $async_filter: expr)?),*))?
+            $(capacity $async_capacity: literal $(when full $async_backpressure: ident)?)?),*)?
+     $(delivery: $delivery: ident)?
+     $(startup: $startup: ident)?
      $(log file: $log_file: expr)?
      ) => {
 
@@ -75,21 +440,72 @@ macro_rules! create_event_loops {
     //                              General structs and types
     // ========================================================================================
 
-    // Create the events enum, containing all structs
+    // Create the events enum, containing all structs.
+    // Events are shared between subscribers: they are never cloned to be routed. Each event
+    // carries the responder of the request which sent it, if any.
     #[derive(::std::clone::Clone)]
     pub enum PelAllEvents {
-        PelInternalExitEvent,
-        $($event_name($event_name),)*
+        PelInternalExitEvent($crate::PelExit),
+        // Sent by the main event loop to every loop when the application exits
+        PelInternalShutdownEvent,
+        // Event to publish at the given instant, held by the main event loop until then
+        PelInternalScheduledEvent(::std::time::Instant, ::std::boxed::Box<PelAllEvents>,
+                                  $crate::PelTimerHandle),
+        // Event which could not be handled, sent to the dead-letter sinks by the main event loop
+        PelInternalDeadLetterEvent(::std::boxed::Box<$crate::PelDeadLetter<PelAllEvents>>),
+        // Event published by a PelPublisher, from outside the loops
+        PelInternalExternalEvent(::std::boxed::Box<PelAllEvents>),
+        $($event_name(::std::sync::Arc<$event_name>,
+                      $crate::PelResponder<<$event_name as $crate::PelEvent>::Response>),)*
+    }
+
+    impl PelAllEvents {
+        /// Events with a higher priority are handled first. The default priority is 0.
+        pub fn priority(&self) -> u8 {
+            match self {
+                $(PelAllEvents::$event_name(..) => $crate::__pel_or_default!($($event_priority)?),)*
+                PelAllEvents::PelInternalExitEvent(_) | PelAllEvents::PelInternalShutdownEvent => 0,
+                PelAllEvents::PelInternalScheduledEvent(_, event, _) => event.priority(),
+                PelAllEvents::PelInternalDeadLetterEvent(dead_letter) => dead_letter.event.priority(),
+                PelAllEvents::PelInternalExternalEvent(event) => event.priority(),
+            }
+        }
+    }
+
+    impl PelAllEvents {
+        /// Key used to send the event to an instance of the loops balanced by HashByKey: the
+        /// hash of the fields marked with #[key], None if there are none.
+        pub fn routing_key(&self) -> ::std::option::Option<u64> {
+            match self {
+                $(PelAllEvents::$event_name([<$event_name:snake>], _) => {
+                    #[allow(unused_mut)]
+                    let mut hasher: ::std::option::Option<
+                        ::std::collections::hash_map::DefaultHasher> = ::std::option::Option::None;
+                    $($($crate::__pel_event_field_attribute!(
+                        $event_field_attribute; hasher, &[<$event_name:snake>].$event_field);)?)*
+                    hasher.map(|hasher| ::std::hash::Hasher::finish(&hasher))
+                },)*
+                _ => ::std::option::Option::None,
+            }
+        }
     }
 
     impl ::std::fmt::Display for PelAllEvents {
         fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
             match &*self {
-                $(PelAllEvents::$event_name([<$event_name:snake>]) =>
+                $(PelAllEvents::$event_name([<$event_name:snake>], _) =>
                   write!(f,
                          concat!("{} : ", $(stringify!($event_field), " = {:?}, "),*),
                          stringify!($event_name), $([<$event_name:snake>].$event_field),*),)*
-                PelInternalExitEvent => write!(f, "Exit Event"),
+                PelAllEvents::PelInternalScheduledEvent(deadline, event, _) =>
+                  write!(f, "{} (scheduled in {:?})", event,
+                         deadline.saturating_duration_since(::std::time::Instant::now())),
+                PelAllEvents::PelInternalDeadLetterEvent(dead_letter) =>
+                  write!(f, "Dead Letter : {}", dead_letter),
+                PelAllEvents::PelInternalExternalEvent(event) =>
+                  write!(f, "{} (published by external)", event),
+                PelAllEvents::PelInternalShutdownEvent => write!(f, "Shutdown Event"),
+                PelAllEvents::PelInternalExitEvent(exit) => write!(f, "Exit Event : {}", exit),
             }
         }
     }
@@ -111,11 +527,29 @@ macro_rules! create_event_loops {
             }
         }
     }
+
+    impl $crate::PelEvent for $event_name {
+        type Response = $crate::__pel_response_type!($($event_response)?);
+    }
+
+    impl ::std::convert::From<$event_name> for PelAllEvents {
+        /// The event is published without waiting for a response.
+        fn from([<$event_name:snake>]: $event_name) -> Self {
+            PelAllEvents::$event_name(::std::sync::Arc::new([<$event_name:snake>]),
+                                      $crate::PelResponder::none())
+        }
+    }
     )*
 
     // Trait to be implemented by every active loop
     pub trait MainLoop {
         fn main_loop(&mut self);
+
+        /// Called by the thread of the loop before it handles any event.
+        fn on_start(&mut self) {}
+
+        /// Called once the loop handled its last event, when the application exits.
+        fn on_shutdown(&mut self) {}
     }
 
     // ========================================================================================
@@ -125,35 +559,73 @@ macro_rules! create_event_loops {
     // For each active event loop, create a custom struct
     $($(
     pub struct $active_loop_name {
-        _pel_internal_event_sender: ::std::sync::mpsc::Sender<PelAllEvents>,
-        _pel_internal_event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
-        $($field_active: $type_active,)*
+        _pel_internal_publisher: PelPublisher,
+        _pel_internal_event_receiver: $crate::PelReceiver<PelAllEvents>,
+        _pel_internal_subscriptions: [<$active_loop_name Subscriptions>],
+        _pel_internal_start_latches: PelStartLatches,
+        _pel_internal_supervisor: $crate::PelSupervisor,
+        _pel_internal_running: bool,
+        $($visibility $field_active: $type_active,)*
     }
 
-    // Create a custom trait with all handlers, must be implemented
-    $(
-        pub trait [<$active_loop_name EventHandlers>] {
-        $(fn [<on_ $event_to_react_to_active:snake>](&mut self,
-                                                     event: $event_to_react_to_active);)*
-        }
+    // Create a custom trait with all handlers, must be implemented if the loop subscribes to
+    // events or receives dead letters
+    pub trait [<$active_loop_name EventHandlers>] {
+        $($(fn [<on_ $event_to_react_to_active:snake>](
+                &mut self,
+                event: $crate::__pel_handler_event_type!(
+                    $($active_passing)?; $event_to_react_to_active))
+            -> <$event_to_react_to_active as $crate::PelEvent>::Response;)*)*
+        $(
+        /// Called with the events which could not be handled by their loop.
+        fn $active_dead_letter_handler(&mut self,
+                                       dead_letter: $crate::PelDeadLetter<PelAllEvents>);
+        )?
+    }
 
-        // Calling this function ensures that the handler trait is implemented by the struct
+    // Calling this function ensures that the handler trait is implemented by the struct
+    $crate::__pel_if_present!(($($($event_to_react_to_active)*)* $($active_dead_letter_handler)?)
         fn [<_pel_assert_ $active_loop_name:snake _implements_its_event_handler_trait>]
-        <T>() where T: [<$active_loop_name EventHandlers>] {}
-    )*
+        <T>() where T: [<$active_loop_name EventHandlers>] {});
 
     // Calling this function ensures that the main loop trait is implemented by the struct
     fn [<_pel_assert_ $active_loop_name:snake _implements_its_main_loop_trait>]
         <T>() where T: MainLoop {}
 
+    /// Events the loop currently receives, see subscribe\_ and unsubscribe\_ functions.
+    #[derive(::std::clone::Clone, ::std::default::Default)]
+    pub struct [<$active_loop_name Subscriptions>] {
+        $($([<$event_to_react_to_active:snake>]: $crate::PelSubscription,)*)*
+    }
+
+    impl [<$active_loop_name Subscriptions>] {
+        fn is_active(&self, event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_active(..) => self.[<$event_to_react_to_active:snake>].is_active(),)*)*
+                _ => false,
+            }
+        }
+    }
+
     impl $active_loop_name {
-        pub fn new(event_sender: ::std::sync::mpsc::Sender<PelAllEvents>,
-                   event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(publisher: PelPublisher,
+                   event_receiver: $crate::PelReceiver<PelAllEvents>,
+                   subscriptions: [<$active_loop_name Subscriptions>],
+                   start_latches: PelStartLatches,
                     $($field_active: $type_active,)*
            ) -> Self {
             $active_loop_name {
-                _pel_internal_event_sender: event_sender,
+                _pel_internal_publisher: PelPublisher {
+                    _pel_internal_source: ::std::option::Option::Some(stringify!($active_loop_name)),
+                    ..publisher
+                },
                 _pel_internal_event_receiver: event_receiver,
+                _pel_internal_subscriptions: subscriptions,
+                _pel_internal_start_latches: start_latches,
+                _pel_internal_supervisor: $crate::PelSupervisor::new($crate::__pel_supervision!(
+                    $($active_supervision $($active_max_restarts $active_restart_window)?)?)),
+                _pel_internal_running: true,
                 $($field_active,)*
             }
         }
@@ -161,48 +633,209 @@ macro_rules! create_event_loops {
         // For each event the active loop can send, create a custom function
         $($(
         /// Sends the event to all threads which are subscribed.
+        ///
+        /// Errors are ignored: use the try\_publish variant to know if the event was refused.
         pub fn [<publish_ $event_to_publish_active:snake>](
             &self, [<$event_to_publish_active:snake>]: $event_to_publish_active) {
-                // An error means we have been disconnected.
-                // If it happens, it means the thread ended, therefore we don't have to notify it
-                // anyway
-                let _ = self._pel_internal_event_sender.send(
-                    PelAllEvents::$event_to_publish_active([<$event_to_publish_active:snake>])
-                );
+                let _ = self.[<try_publish_ $event_to_publish_active:snake>]([<$event_to_publish_active:snake>]);
+        }
+
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Fails if the main event loop is disconnected or if the queue of a subscribed loop is
+        /// full and its backpressure policy is Error.
+        pub fn [<try_publish_ $event_to_publish_active:snake>](
+            &self, [<$event_to_publish_active:snake>]: $event_to_publish_active)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+                self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_active(
+                    ::std::sync::Arc::new([<$event_to_publish_active:snake>]),
+                    $crate::PelResponder::none()))
+        }
+
+        /// Sends the event to all threads which are subscribed and returns a handle to wait
+        /// for the response of the first handler.
+        pub fn [<request_ $event_to_publish_active:snake>](
+            &self, [<$event_to_publish_active:snake>]: $event_to_publish_active)
+            -> $crate::PelResponse<<$event_to_publish_active as $crate::PelEvent>::Response> {
+                let (responder, response) = $crate::PelResponder::new();
+                // If the request is refused, it is dropped with its responder: the handle
+                // then reports that there is no response
+                let _ = self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_active(
+                    ::std::sync::Arc::new([<$event_to_publish_active:snake>]), responder));
+                response
+        }
+
+        /// Publishes the event once the delay has elapsed, unless the returned handle is
+        /// cancelled before.
+        pub fn [<publish_ $event_to_publish_active:snake _after>](
+            &self, delay: ::std::time::Duration, [<$event_to_publish_active:snake>]: $event_to_publish_active)
+            -> $crate::PelTimerHandle {
+                self.[<publish_ $event_to_publish_active:snake _at>](
+                    ::std::time::Instant::now() + delay, [<$event_to_publish_active:snake>])
+        }
+
+        /// Publishes the event at the given instant, unless the returned handle is cancelled
+        /// before. The main event loop holds the event until then.
+        pub fn [<publish_ $event_to_publish_active:snake _at>](
+            &self, instant: ::std::time::Instant, [<$event_to_publish_active:snake>]: $event_to_publish_active)
+            -> $crate::PelTimerHandle {
+                let handle = $crate::PelTimerHandle::new();
+                let _ = self._pel_internal_publisher._pel_internal_event_sender.send(
+                    PelAllEvents::PelInternalScheduledEvent(
+                        instant,
+                        ::std::boxed::Box::new(PelAllEvents::$event_to_publish_active(
+                            ::std::sync::Arc::new([<$event_to_publish_active:snake>]),
+                            $crate::PelResponder::none())),
+                        handle.clone()));
+                handle
         }
         )*)*
 
         pub const fn is_subscribed_to_event(event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_active(..) => true,)*)*
+                _ => false,
+            }
+        }
+
+        /// Returns true if the loop is subscribed to the event and the filter of its
+        /// subscription, if any, accepts it.
+        pub fn accepts_event(event: &PelAllEvents) -> bool {
             match event {
                 $($(PelAllEvents::$event_to_react_to_active(
-                        [<$event_to_react_to_active:snake>]) => true,)*)*
+                        [<$event_to_react_to_active:snake>], _) =>
+                    $crate::__pel_filter!([<$event_to_react_to_active:snake>];
+                                          $($active_filter)?),)*)*
                 _ => false,
             }
         }
 
+        $($(
+        /// Routes the event to the loop again after unsubscribe\_{event}. Subscriptions are
+        /// shared by every instance of the loop.
+        pub fn [<subscribe_ $event_to_react_to_active:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_active:snake>].subscribe();
+        }
+
+        /// Stops routing the event to the loop. Events already queued are still handled.
+        pub fn [<unsubscribe_ $event_to_react_to_active:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_active:snake>].unsubscribe();
+        }
+        )*)*
+
+        /// How many pending events process\_events handles before returning.
+        pub const DRAIN: $crate::PelDrain = $crate::__pel_drain!($($active_drain)?);
+
         /// For each event the active loop can receive, call a custom handler.
+        ///
+        /// Handles the pending events without blocking, as many as allowed by the DRAIN
+        /// setting of the loop.
         pub fn process_events(&mut self) {
-            match self._pel_internal_event_receiver.try_recv() {
-                Ok(event) => match event {
-                    $($(PelAllEvents::$event_to_react_to_active(
-                            [<$event_to_react_to_active:snake>]) =>
-                        self.[<on_ $event_to_react_to_active:snake>](
-                            [<$event_to_react_to_active:snake>]),)*)*
-                    _ => panic!("Unhandled event"),
-                },
-                Err(::std::sync::mpsc::TryRecvError::Empty) => {
-                    // Do nothing if no event is received
-                },
-                Err(::std::sync::mpsc::TryRecvError::Disconnected) => {
-                    // Disconnected from main thread
-                    ::std::process::exit(0);
+            let n_max_events =
+                Self::DRAIN.n_max_events(self._pel_internal_event_receiver.len());
+            for _ in 0..n_max_events {
+                match self._pel_internal_event_receiver.try_recv() {
+                    Ok(event) => match event {
+                        $($(PelAllEvents::$event_to_react_to_active(
+                                [<$event_to_react_to_active:snake>], responder) =>
+                            responder.respond(self.[<on_ $event_to_react_to_active:snake>](
+                                $crate::__pel_handler_event!($($active_passing)?;
+                                    [<$event_to_react_to_active:snake>]))),)*)*
+                        PelAllEvents::PelInternalShutdownEvent => {
+                            self._pel_internal_shut_down();
+                            break;
+                        },
+                        $(PelAllEvents::PelInternalDeadLetterEvent(dead_letter) =>
+                            self.$active_dead_letter_handler(*dead_letter),)?
+                        event => self._pel_internal_publisher._pel_internal_send_dead_letter(
+                            event, "the loop has no handler for this event",
+                            stringify!($active_loop_name)),
+                    },
+                    Err(::std::sync::mpsc::TryRecvError::Empty) => {
+                        // Do nothing if no event is received
+                        break;
+                    },
+                    Err(::std::sync::mpsc::TryRecvError::Disconnected) => {
+                        // Disconnected from main thread
+                        self._pel_internal_shut_down();
+                        break;
+                    }
+                }
+            }
+        }
+
+        /// Returns false once the loop was shut down: its thread then ends.
+        pub fn is_running(&self) -> bool {
+            self._pel_internal_running
+        }
+
+        /// Waits for the loops it starts after, then calls on\_start. With a startup barrier,
+        /// then waits for every loop to be started.
+        ///
+        /// Called by the thread of the loop before it handles any event.
+        pub fn start(&mut self) {
+            $($(self._pel_internal_start_latches.[<$active_dependency:snake>].wait();)*)*
+            self.supervise(|event_loop| MainLoop::on_start(event_loop));
+            self._pel_internal_start_latches.[<$active_loop_name:snake>].count_down();
+            if self._pel_internal_start_latches.startup == $crate::PelStartup::Barrier {
+                self._pel_internal_start_latches.wait_all();
+            }
+        }
+
+        fn _pel_internal_shut_down(&mut self) {
+            if self._pel_internal_running {
+                self._pel_internal_running = false;
+                MainLoop::on_shutdown(self);
+            }
+        }
+
+        /// Runs f, applying the supervision policy of the loop if it panics. Crashes and restarts
+        /// are logged.
+        ///
+        /// The thread of the loop runs its hooks and handlers through this function.
+        pub fn supervise(&mut self, f: impl ::std::ops::FnOnce(&mut Self)) {
+            let mut result = ::std::panic::catch_unwind(
+                ::std::panic::AssertUnwindSafe(|| f(self)));
+            while let Err(panic) = result {
+                result = Ok(());
+                let message = $crate::pel_panic_message(&*panic);
+                match self._pel_internal_supervisor.on_panic() {
+                    $crate::PelSupervisorAction::Escalate => {
+                        ::log::error!("{} panicked: {}", stringify!($active_loop_name), message);
+                        let _ = self.exit_with(101, ::std::format!(
+                            "{} panicked: {}", stringify!($active_loop_name), message));
+                        // The thread of the loop ends, its pending events are not handled
+                        self._pel_internal_running = false;
+                    },
+                    $crate::PelSupervisorAction::Restart if !self._pel_internal_running => {
+                        // A loop which is shutting down is not restarted
+                        ::log::error!("{} panicked while shutting down: {}", stringify!($active_loop_name), message);
+                    },
+                    $crate::PelSupervisorAction::Restart => {
+                        ::log::error!("{} panicked: {}, restarting it", stringify!($active_loop_name), message);
+                        $(self.$field_active = $init_field_active;)*
+                        result = ::std::panic::catch_unwind(
+                            ::std::panic::AssertUnwindSafe(|| MainLoop::on_start(self)));
+                    },
+                    $crate::PelSupervisorAction::Ignore => {
+                        ::log::error!("{} panicked: {}, ignoring it", stringify!($active_loop_name), message);
+                    },
                 }
             }
         }
 
-        /// Exit the application. Terminates all threads.
-        pub fn exit(&self) -> Result<(), ::std::sync::mpsc::SendError<PelAllEvents>> {
-            self._pel_internal_event_sender.send(PelAllEvents::PelInternalExitEvent {})
+        /// Exit the application: every loop is shut down once it handled its pending events.
+        pub fn exit(&self) -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self.exit_with(0, concat!(stringify!($active_loop_name), " exited"))
+        }
+
+        /// Exit the application like exit, with the code and reason returned by pel\_main.
+        ///
+        /// Only the first exit of the application is taken into account.
+        pub fn exit_with(&self, code: i32, reason: impl ::std::convert::Into<::std::string::String>)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publisher._pel_internal_event_sender.send(
+                PelAllEvents::PelInternalExitEvent($crate::PelExit::new(code, reason)))
         }
     }
     )*)*
@@ -215,29 +848,88 @@ macro_rules! create_event_loops {
     // For each active event loop, create a custom struct
     $($(
     pub struct $reactive_loop_name {
-        _pel_internal_event_sender: ::std::sync::mpsc::Sender<PelAllEvents>,
-        _pel_internal_event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
-        $($field_reactive: $type_reactive,)*
+        _pel_internal_publisher: PelPublisher,
+        _pel_internal_event_receiver: $crate::PelReceiver<PelAllEvents>,
+        _pel_internal_subscriptions: [<$reactive_loop_name Subscriptions>],
+        _pel_internal_start_latches: PelStartLatches,
+        _pel_internal_supervisor: $crate::PelSupervisor,
+        _pel_internal_running: bool,
+        _pel_internal_started: bool,
+        _pel_internal_ticker: ::std::option::Option<$crate::PelTicker>,
+        _pel_internal_idle_timer: ::std::option::Option<$crate::PelIdleTimer>,
+        $($visibility $field_reactive: $type_reactive,)*
     }
 
-    // Create a custom trait with all handlers, must be implemented
-    $(pub trait [<$reactive_loop_name EventHandlers>] {
-        $(fn [<on_ $event_to_react_to_reactive:snake>](
+    // Create a custom trait with all handlers, must be implemented if the loop subscribes to
+    // events, ticks, has an idle timeout or receives dead letters
+    pub trait [<$reactive_loop_name EventHandlers>] {
+        /// Called by the thread of the loop before it handles any event.
+        fn on_start(&mut self) {}
+
+        /// Called once the loop handled its last event, when the application exits.
+        fn on_shutdown(&mut self) {}
+
+        $($(fn [<on_ $event_to_react_to_reactive:snake>](
                 &mut self,
-                event: $event_to_react_to_reactive);)*
+                event: $crate::__pel_handler_event_type!(
+                    $($reactive_passing)?; $event_to_react_to_reactive))
+            -> <$event_to_react_to_reactive as $crate::PelEvent>::Response;)*)*
+        $(
+        /// Called with the events which could not be handled by their loop.
+        fn $reactive_dead_letter_handler(&mut self,
+                                         dead_letter: $crate::PelDeadLetter<PelAllEvents>);
+        )?
+        $(
+        #[doc = concat!("Called every ", stringify!($reactive_period), ".")]
+        fn $reactive_tick(&mut self);
+        )?
+
+        /// Called when the loop got no event for the duration given by `idle after`, then
+        /// again each time it elapses until the loop gets one. Never called without `idle after`.
+        fn on_idle(&mut self, _idle_for: ::std::time::Duration) {}
     }
     // Calling this function ensures that the trait is implemented by the struct
     fn [<_pel_assert_ $reactive_loop_name:snake _implements_its_event_handler_trait>]
         <T>() where T: [<$reactive_loop_name EventHandlers>] {}
-    )*
+    /// Events the loop currently receives, see subscribe\_ and unsubscribe\_ functions.
+    #[derive(::std::clone::Clone, ::std::default::Default)]
+    pub struct [<$reactive_loop_name Subscriptions>] {
+        $($([<$event_to_react_to_reactive:snake>]: $crate::PelSubscription,)*)*
+    }
+
+    impl [<$reactive_loop_name Subscriptions>] {
+        fn is_active(&self, event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_reactive(..) => self.[<$event_to_react_to_reactive:snake>].is_active(),)*)*
+                _ => false,
+            }
+        }
+    }
+
     impl $reactive_loop_name {
-        pub fn new(event_sender: ::std::sync::mpsc::Sender<PelAllEvents>,
-                   event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(publisher: PelPublisher,
+                   event_receiver: $crate::PelReceiver<PelAllEvents>,
+                   subscriptions: [<$reactive_loop_name Subscriptions>],
+                   start_latches: PelStartLatches,
                    $($field_reactive: $type_reactive,)*
            ) -> Self {
             $reactive_loop_name {
-                _pel_internal_event_sender: event_sender,
+                _pel_internal_publisher: PelPublisher {
+                    _pel_internal_source: ::std::option::Option::Some(stringify!($reactive_loop_name)),
+                    ..publisher
+                },
                 _pel_internal_event_receiver: event_receiver,
+                _pel_internal_subscriptions: subscriptions,
+                _pel_internal_start_latches: start_latches,
+                _pel_internal_supervisor: $crate::PelSupervisor::new($crate::__pel_supervision!(
+                    $($reactive_supervision $($reactive_max_restarts $reactive_restart_window)?)?)),
+                _pel_internal_running: true,
+                _pel_internal_started: false,
+                _pel_internal_ticker: $crate::__pel_or_default!($(::std::option::Option::Some(
+                    $crate::PelTicker::new($crate::__pel_duration!($reactive_period))))?),
+                _pel_internal_idle_timer: $crate::__pel_or_default!($(::std::option::Option::Some(
+                    $crate::PelIdleTimer::new($crate::__pel_duration!($reactive_idle_timeout))))?),
                 $($field_reactive,)*
             }
         }
@@ -245,127 +937,1036 @@ macro_rules! create_event_loops {
         // For each event the reactive loop can send, create a custom function
         $($(
         /// Sends the event to all threads which are subscribed.
-        pub fn [<publish _$event_to_publish_reactive:snake>](
+        ///
+        /// Errors are ignored: use the try\_publish variant to know if the event was refused.
+        pub fn [<publish_ $event_to_publish_reactive:snake>](
             &self, [<$event_to_publish_reactive:snake>]: $event_to_publish_reactive) {
-                // An error means we have been disconnected.
-                // If it happens, it means the thread ended, therefore we don't have to notify it
-                // anyway
-                let _ = self._pel_internal_event_sender.send(
-                    PelAllEvents::$event_to_publish_reactive([<$event_to_publish_reactive:snake>])
-                );
+                let _ = self.[<try_publish_ $event_to_publish_reactive:snake>]([<$event_to_publish_reactive:snake>]);
+        }
+
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Fails if the main event loop is disconnected or if the queue of a subscribed loop is
+        /// full and its backpressure policy is Error.
+        pub fn [<try_publish_ $event_to_publish_reactive:snake>](
+            &self, [<$event_to_publish_reactive:snake>]: $event_to_publish_reactive)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+                self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_reactive(
+                    ::std::sync::Arc::new([<$event_to_publish_reactive:snake>]),
+                    $crate::PelResponder::none()))
+        }
+
+        /// Sends the event to all threads which are subscribed and returns a handle to wait
+        /// for the response of the first handler.
+        pub fn [<request_ $event_to_publish_reactive:snake>](
+            &self, [<$event_to_publish_reactive:snake>]: $event_to_publish_reactive)
+            -> $crate::PelResponse<<$event_to_publish_reactive as $crate::PelEvent>::Response> {
+                let (responder, response) = $crate::PelResponder::new();
+                // If the request is refused, it is dropped with its responder: the handle
+                // then reports that there is no response
+                let _ = self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_reactive(
+                    ::std::sync::Arc::new([<$event_to_publish_reactive:snake>]), responder));
+                response
+        }
+
+        /// Publishes the event once the delay has elapsed, unless the returned handle is
+        /// cancelled before.
+        pub fn [<publish_ $event_to_publish_reactive:snake _after>](
+            &self, delay: ::std::time::Duration, [<$event_to_publish_reactive:snake>]: $event_to_publish_reactive)
+            -> $crate::PelTimerHandle {
+                self.[<publish_ $event_to_publish_reactive:snake _at>](
+                    ::std::time::Instant::now() + delay, [<$event_to_publish_reactive:snake>])
+        }
+
+        /// Publishes the event at the given instant, unless the returned handle is cancelled
+        /// before. The main event loop holds the event until then.
+        pub fn [<publish_ $event_to_publish_reactive:snake _at>](
+            &self, instant: ::std::time::Instant, [<$event_to_publish_reactive:snake>]: $event_to_publish_reactive)
+            -> $crate::PelTimerHandle {
+                let handle = $crate::PelTimerHandle::new();
+                let _ = self._pel_internal_publisher._pel_internal_event_sender.send(
+                    PelAllEvents::PelInternalScheduledEvent(
+                        instant,
+                        ::std::boxed::Box::new(PelAllEvents::$event_to_publish_reactive(
+                            ::std::sync::Arc::new([<$event_to_publish_reactive:snake>]),
+                            $crate::PelResponder::none())),
+                        handle.clone()));
+                handle
         }
         )*)*
 
         pub const fn is_subscribed_to_event(event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_reactive(..) => true,)*)*
+                _ => false,
+            }
+        }
+
+        /// Returns true if the loop is subscribed to the event and the filter of its
+        /// subscription, if any, accepts it.
+        pub fn accepts_event(event: &PelAllEvents) -> bool {
             match event {
                 $($(PelAllEvents::$event_to_react_to_reactive(
-                        [<$event_to_react_to_reactive:snake>]) => true,)*)*
+                        [<$event_to_react_to_reactive:snake>], _) =>
+                    $crate::__pel_filter!([<$event_to_react_to_reactive:snake>];
+                                          $($reactive_filter)?),)*)*
                 _ => false,
             }
         }
 
+        $($(
+        /// Routes the event to the loop again after unsubscribe\_{event}. Subscriptions are
+        /// shared by every instance of the loop.
+        pub fn [<subscribe_ $event_to_react_to_reactive:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_reactive:snake>].subscribe();
+        }
+
+        /// Stops routing the event to the loop. Events already queued are still handled.
+        pub fn [<unsubscribe_ $event_to_react_to_reactive:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_reactive:snake>].unsubscribe();
+        }
+        )*)*
+
         /// For each event the reactive loop can receive, call a custom handler.
+        ///
+        /// Loops which tick or have an idle timeout wait for an event until the next tick or
+        /// timeout, and handle them first if they are due.
         pub fn process_events(&mut self) {
-            match self._pel_internal_event_receiver.recv() {
+            let received = match self.next_deadline() {
+                ::std::option::Option::None => self._pel_internal_event_receiver.recv()
+                    .map_err(|_| ::std::sync::mpsc::RecvTimeoutError::Disconnected),
+                ::std::option::Option::Some(deadline) => {
+                    let now = ::std::time::Instant::now();
+                    if deadline <= now {
+                        Err(::std::sync::mpsc::RecvTimeoutError::Timeout)
+                    } else {
+                        self._pel_internal_event_receiver.recv_timeout(deadline - now)
+                    }
+                },
+            };
+            self._pel_internal_handle(received);
+        }
+
+        /// Like process\_events, without blocking: returns false if there was no event pending
+        /// and neither the tick nor the idle timeout were due.
+        pub fn try_process_events(&mut self) -> bool {
+            let received = match self._pel_internal_event_receiver.try_recv() {
+                Ok(event) => Ok(event),
+                Err(::std::sync::mpsc::TryRecvError::Empty) => {
+                    if self.next_deadline()
+                        .is_some_and(|deadline| deadline <= ::std::time::Instant::now()) {
+                        Err(::std::sync::mpsc::RecvTimeoutError::Timeout)
+                    } else {
+                        return false;
+                    }
+                },
+                Err(::std::sync::mpsc::TryRecvError::Disconnected) =>
+                    Err(::std::sync::mpsc::RecvTimeoutError::Disconnected),
+            };
+            self._pel_internal_handle(received);
+            true
+        }
+
+        /// Returns when the next tick or idle timeout is due, None if the loop has neither.
+        pub fn next_deadline(&self) -> ::std::option::Option<::std::time::Instant> {
+            [
+                self._pel_internal_ticker.as_ref().map(|ticker| ticker.next_tick()),
+                self._pel_internal_idle_timer.as_ref().map(|idle_timer| idle_timer.deadline()),
+            ].iter().flatten().min().copied()
+        }
+
+        fn _pel_internal_handle(&mut self,
+                                received: Result<PelAllEvents, ::std::sync::mpsc::RecvTimeoutError>) {
+            if received.is_ok() {
+                if let ::std::option::Option::Some(idle_timer) = &mut self._pel_internal_idle_timer {
+                    idle_timer.reset(::std::time::Instant::now());
+                }
+            }
+            match received {
                 Ok(event) => match event {
                     $($(PelAllEvents::$event_to_react_to_reactive(
-                            [<$event_to_react_to_reactive:snake>]) =>
-                        self.[<on_ $event_to_react_to_reactive:snake>](
-                            [<$event_to_react_to_reactive:snake>].clone()),)*)*
-                    _ => panic!("Unhandled event"),
+                            [<$event_to_react_to_reactive:snake>], responder) =>
+                        responder.respond(self.[<on_ $event_to_react_to_reactive:snake>](
+                            $crate::__pel_handler_event!($($reactive_passing)?;
+                                [<$event_to_react_to_reactive:snake>]))),)*)*
+                    PelAllEvents::PelInternalShutdownEvent => self._pel_internal_shut_down(),
+                    $(PelAllEvents::PelInternalDeadLetterEvent(dead_letter) =>
+                        self.$reactive_dead_letter_handler(*dead_letter),)?
+                    event => self._pel_internal_publisher._pel_internal_send_dead_letter(
+                        event, "the loop has no handler for this event",
+                        stringify!($reactive_loop_name)),
+                },
+                Err(::std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    $(
+                    if self._pel_internal_ticker.as_mut()
+                        .is_some_and(|ticker| ticker.poll(::std::time::Instant::now())) {
+                        self.$reactive_tick();
+                    }
+                    )?
+                    // Without idle after, there is no idle timer
+                    if let ::std::option::Option::Some(idle_for) = self._pel_internal_idle_timer
+                        .as_mut().and_then(|idle_timer| idle_timer.poll(::std::time::Instant::now())) {
+                        self.on_idle(idle_for);
+                    }
                 },
-                Err(::std::sync::mpsc::RecvError) => {
+                Err(::std::sync::mpsc::RecvTimeoutError::Disconnected) => {
                     // Disconnected from main thread
-                    ::std::process::exit(0);
+                    self._pel_internal_shut_down();
                 },
             }
         }
 
-        /// Exit the application. Terminates all threads.
-        pub fn exit(&self) -> Result<(), ::std::sync::mpsc::SendError<PelAllEvents>> {
-            self._pel_internal_event_sender.send(PelAllEvents::PelInternalExitEvent {})
+        /// Returns false once the loop was shut down: its thread then ends.
+        pub fn is_running(&self) -> bool {
+            self._pel_internal_running
         }
-    }
-    )*)*
 
-    // ========================================================================================
-    //                              Main event loop
-    // ========================================================================================
+        /// Waits for the loops it starts after, then calls on\_start. With a startup barrier,
+        /// then waits for every loop to be started.
+        ///
+        /// Called by the thread of the loop before it handles any event.
+        pub fn start(&mut self) {
+            $($(self._pel_internal_start_latches.[<$reactive_dependency:snake>].wait();)*)*
+            self.supervise(|event_loop| [<$reactive_loop_name EventHandlers>]::on_start(event_loop));
+            self._pel_internal_started = true;
+            self._pel_internal_start_latches.[<$reactive_loop_name:snake>].count_down();
+            if self._pel_internal_start_latches.startup == $crate::PelStartup::Barrier {
+                self._pel_internal_start_latches.wait_all();
+            }
+        }
 
-    pub struct PelMainEventLoop {
-        _pel_internal_event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
-        $($(
-            [<_pel_internal_ $reactive_loop_name:snake _event_sender>]:
-                ::std::sync::mpsc::Sender<PelAllEvents>,
-        )*)*
-        $($(
-            [<_pel_internal_ $active_loop_name:snake _event_sender>]:
-                ::std::sync::mpsc::Sender<PelAllEvents>,
-        )*)*
-    }
+        /// Like start, without blocking: returns false while a loop it starts after is not
+        /// started, or while the startup barrier holds the events back. on\_start is called once.
+        pub fn try_start(&mut self) -> bool {
+            if !self._pel_internal_started {
+                if !(true $($(&& self._pel_internal_start_latches.[<$reactive_dependency:snake>]
+                        .is_open())*)*) {
+                    return false;
+                }
+                self.supervise(|event_loop| [<$reactive_loop_name EventHandlers>]::on_start(event_loop));
+                self._pel_internal_started = true;
+                self._pel_internal_start_latches.[<$reactive_loop_name:snake>].count_down();
+            }
+            self._pel_internal_start_latches.startup != $crate::PelStartup::Barrier
+                || self._pel_internal_start_latches.are_all_open()
+        }
 
-    impl PelMainEventLoop {
-        pub fn new(event_receiver: ::std::sync::mpsc::Receiver<PelAllEvents>,
-            $($(
-            [<$reactive_loop_name:snake _event_sender>]:
-                ::std::sync::mpsc::Sender<PelAllEvents>,
-            )*)*
-            $($(
+        fn _pel_internal_shut_down(&mut self) {
+            if self._pel_internal_running {
+                self._pel_internal_running = false;
+                [<$reactive_loop_name EventHandlers>]::on_shutdown(self);
+            }
+        }
+
+        /// Runs f, applying the supervision policy of the loop if it panics. Crashes and restarts
+        /// are logged.
+        ///
+        /// The thread of the loop runs its hooks and handlers through this function.
+        pub fn supervise(&mut self, f: impl ::std::ops::FnOnce(&mut Self)) {
+            let mut result = ::std::panic::catch_unwind(
+                ::std::panic::AssertUnwindSafe(|| f(self)));
+            while let Err(panic) = result {
+                result = Ok(());
+                let message = $crate::pel_panic_message(&*panic);
+                match self._pel_internal_supervisor.on_panic() {
+                    $crate::PelSupervisorAction::Escalate => {
+                        ::log::error!("{} panicked: {}", stringify!($reactive_loop_name), message);
+                        let _ = self.exit_with(101, ::std::format!(
+                            "{} panicked: {}", stringify!($reactive_loop_name), message));
+                        // The thread of the loop ends, its pending events are not handled
+                        self._pel_internal_running = false;
+                    },
+                    $crate::PelSupervisorAction::Restart if !self._pel_internal_running => {
+                        // A loop which is shutting down is not restarted
+                        ::log::error!("{} panicked while shutting down: {}", stringify!($reactive_loop_name), message);
+                    },
+                    $crate::PelSupervisorAction::Restart => {
+                        ::log::error!("{} panicked: {}, restarting it", stringify!($reactive_loop_name), message);
+                        $(self.$field_reactive = $init_field_reactive;)*
+                        result = ::std::panic::catch_unwind(
+                            ::std::panic::AssertUnwindSafe(|| [<$reactive_loop_name EventHandlers>]::on_start(self)));
+                    },
+                    $crate::PelSupervisorAction::Ignore => {
+                        ::log::error!("{} panicked: {}, ignoring it", stringify!($reactive_loop_name), message);
+                    },
+                }
+            }
+        }
+
+        /// Exit the application: every loop is shut down once it handled its pending events.
+        pub fn exit(&self) -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self.exit_with(0, concat!(stringify!($reactive_loop_name), " exited"))
+        }
+
+        /// Exit the application like exit, with the code and reason returned by pel\_main.
+        ///
+        /// Only the first exit of the application is taken into account.
+        pub fn exit_with(&self, code: i32, reason: impl ::std::convert::Into<::std::string::String>)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publisher._pel_internal_event_sender.send(
+                PelAllEvents::PelInternalExitEvent($crate::PelExit::new(code, reason)))
+        }
+    }
+
+    // Lets pel_launch_event_loops_in_workers schedule the loop onto its worker threads
+    impl $crate::PelTask for $reactive_loop_name {
+        fn name(&self) -> &'static str {
+            stringify!($reactive_loop_name)
+        }
+
+        fn set_waker(&mut self, waker: $crate::PelWaker) {
+            self._pel_internal_event_receiver.set_waker(move || waker.wake());
+        }
+
+        fn run(&mut self, max_events: usize) -> $crate::PelTaskStatus {
+            if !self.try_start() {
+                // Latches cannot wake the loop up: check them again shortly
+                return $crate::PelTaskStatus::Waiting(::std::option::Option::Some(
+                    ::std::time::Instant::now() + $crate::PEL_START_POLL_PERIOD));
+            }
+            for _ in 0..max_events {
+                if !self.is_running() {
+                    return $crate::PelTaskStatus::Done;
+                }
+                // After a panic, there may still be events pending
+                let mut has_processed_events = true;
+                self.supervise(|event_loop| has_processed_events = event_loop.try_process_events());
+                if !has_processed_events {
+                    return $crate::PelTaskStatus::Waiting(self.next_deadline());
+                }
+            }
+            if self.is_running() {
+                $crate::PelTaskStatus::Ready
+            } else {
+                $crate::PelTaskStatus::Done
+            }
+        }
+    }
+    )*)*
+
+    // ========================================================================================
+    //                              Async event loops
+    // ========================================================================================
+
+    $crate::__pel_if_present!(($($($async_loop_name)*)?) $crate::__pel_require_async_feature!(););
+
+    // For each async event loop, create a custom struct
+    $($(
+    pub struct $async_loop_name {
+        _pel_internal_publisher: PelPublisher,
+        _pel_internal_event_receiver: $crate::PelReceiver<PelAllEvents>,
+        // Notified by the queue of the loop whenever an event is sent to it
+        _pel_internal_event_notify: ::std::sync::Arc<$crate::__pel_tokio::sync::Notify>,
+        _pel_internal_subscriptions: [<$async_loop_name Subscriptions>],
+        _pel_internal_running: bool,
+        $($visibility $field_async: $type_async,)*
+    }
+
+    // Create a custom trait with all handlers, must be implemented by every async loop. The
+    // handlers can be written as async fn, their futures must be Send.
+    pub trait [<$async_loop_name EventHandlers>] {
+        /// Called on the runtime before the loop handles any event.
+        fn on_start(&mut self) -> impl ::std::future::Future<Output = ()> + ::std::marker::Send {
+            async {}
+        }
+
+        /// Called once the loop handled its last event, when the application exits.
+        fn on_shutdown(&mut self) -> impl ::std::future::Future<Output = ()> + ::std::marker::Send {
+            async {}
+        }
+
+        $($(fn [<on_ $event_to_react_to_async:snake>](
+                &mut self,
+                event: $crate::__pel_handler_event_type!(
+                    $($async_passing)?; $event_to_react_to_async))
+            -> impl ::std::future::Future<
+                Output = <$event_to_react_to_async as $crate::PelEvent>::Response>
+                + ::std::marker::Send;)*)*
+    }
+
+    /// Events the loop currently receives, see subscribe\_ and unsubscribe\_ functions.
+    #[derive(::std::clone::Clone, ::std::default::Default)]
+    pub struct [<$async_loop_name Subscriptions>] {
+        $($([<$event_to_react_to_async:snake>]: $crate::PelSubscription,)*)*
+    }
+
+    impl [<$async_loop_name Subscriptions>] {
+        fn is_active(&self, event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_async(..) => self.[<$event_to_react_to_async:snake>].is_active(),)*)*
+                _ => false,
+            }
+        }
+    }
+
+    impl $async_loop_name {
+        pub fn new(publisher: PelPublisher,
+                   event_receiver: $crate::PelReceiver<PelAllEvents>,
+                   subscriptions: [<$async_loop_name Subscriptions>],
+                   $($field_async: $type_async,)*
+           ) -> Self {
+            let event_notify = ::std::sync::Arc::new($crate::__pel_tokio::sync::Notify::new());
+            let waker_event_notify = event_notify.clone();
+            event_receiver.set_waker(move || waker_event_notify.notify_one());
+            $async_loop_name {
+                _pel_internal_publisher: PelPublisher {
+                    _pel_internal_source: ::std::option::Option::Some(stringify!($async_loop_name)),
+                    ..publisher
+                },
+                _pel_internal_event_receiver: event_receiver,
+                _pel_internal_event_notify: event_notify,
+                _pel_internal_subscriptions: subscriptions,
+                _pel_internal_running: true,
+                $($field_async,)*
+            }
+        }
+
+        // For each event the async loop can send, create a custom function
+        $($(
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Errors are ignored: use the try\_publish variant to know if the event was refused.
+        pub fn [<publish_ $event_to_publish_async:snake>](
+            &self, [<$event_to_publish_async:snake>]: $event_to_publish_async) {
+                let _ = self.[<try_publish_ $event_to_publish_async:snake>]([<$event_to_publish_async:snake>]);
+        }
+
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Fails if the main event loop is disconnected or if the queue of a subscribed loop is
+        /// full and its backpressure policy is Error.
+        pub fn [<try_publish_ $event_to_publish_async:snake>](
+            &self, [<$event_to_publish_async:snake>]: $event_to_publish_async)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+                self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_async(
+                    ::std::sync::Arc::new([<$event_to_publish_async:snake>]),
+                    $crate::PelResponder::none()))
+        }
+
+        /// Sends the event to all threads which are subscribed and returns a handle to wait
+        /// for the response of the first handler.
+        pub fn [<request_ $event_to_publish_async:snake>](
+            &self, [<$event_to_publish_async:snake>]: $event_to_publish_async)
+            -> $crate::PelResponse<<$event_to_publish_async as $crate::PelEvent>::Response> {
+                let (responder, response) = $crate::PelResponder::new();
+                // If the request is refused, it is dropped with its responder: the handle
+                // then reports that there is no response
+                let _ = self._pel_internal_publisher._pel_internal_publish(PelAllEvents::$event_to_publish_async(
+                    ::std::sync::Arc::new([<$event_to_publish_async:snake>]), responder));
+                response
+        }
+
+        /// Publishes the event once the delay has elapsed, unless the returned handle is
+        /// cancelled before.
+        pub fn [<publish_ $event_to_publish_async:snake _after>](
+            &self, delay: ::std::time::Duration, [<$event_to_publish_async:snake>]: $event_to_publish_async)
+            -> $crate::PelTimerHandle {
+                self.[<publish_ $event_to_publish_async:snake _at>](
+                    ::std::time::Instant::now() + delay, [<$event_to_publish_async:snake>])
+        }
+
+        /// Publishes the event at the given instant, unless the returned handle is cancelled
+        /// before. The main event loop holds the event until then.
+        pub fn [<publish_ $event_to_publish_async:snake _at>](
+            &self, instant: ::std::time::Instant, [<$event_to_publish_async:snake>]: $event_to_publish_async)
+            -> $crate::PelTimerHandle {
+                let handle = $crate::PelTimerHandle::new();
+                let _ = self._pel_internal_publisher._pel_internal_event_sender.send(
+                    PelAllEvents::PelInternalScheduledEvent(
+                        instant,
+                        ::std::boxed::Box::new(PelAllEvents::$event_to_publish_async(
+                            ::std::sync::Arc::new([<$event_to_publish_async:snake>]),
+                            $crate::PelResponder::none())),
+                        handle.clone()));
+                handle
+        }
+        )*)*
+
+        pub const fn is_subscribed_to_event(event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_async(..) => true,)*)*
+                _ => false,
+            }
+        }
+
+        /// Returns true if the loop is subscribed to the event and the filter of its
+        /// subscription, if any, accepts it.
+        pub fn accepts_event(event: &PelAllEvents) -> bool {
+            match event {
+                $($(PelAllEvents::$event_to_react_to_async(
+                        [<$event_to_react_to_async:snake>], _) =>
+                    $crate::__pel_filter!([<$event_to_react_to_async:snake>];
+                                          $($async_filter)?),)*)*
+                _ => false,
+            }
+        }
+
+        $($(
+        /// Routes the event to the loop again after unsubscribe\_{event}.
+        pub fn [<subscribe_ $event_to_react_to_async:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_async:snake>].subscribe();
+        }
+
+        /// Stops routing the event to the loop. Events already queued are still handled.
+        pub fn [<unsubscribe_ $event_to_react_to_async:snake>](&self) {
+            self._pel_internal_subscriptions.[<$event_to_react_to_async:snake>].unsubscribe();
+        }
+        )*)*
+
+        /// For each event the async loop can receive, await a custom handler.
+        ///
+        /// Waits for an event without blocking the thread of the runtime.
+        pub async fn process_events(&mut self) {
+            let received = loop {
+                match self._pel_internal_event_receiver.try_recv() {
+                    // A permit is stored if the queue notifies before the wait
+                    Err(::std::sync::mpsc::TryRecvError::Empty) =>
+                        self._pel_internal_event_notify.notified().await,
+                    received => break received,
+                }
+            };
+            match received {
+                Ok(event) => match event {
+                    $($(PelAllEvents::$event_to_react_to_async(
+                            [<$event_to_react_to_async:snake>], responder) =>
+                        responder.respond(self.[<on_ $event_to_react_to_async:snake>](
+                            $crate::__pel_handler_event!($($async_passing)?;
+                                [<$event_to_react_to_async:snake>])).await),)*)*
+                    event => self._pel_internal_publisher._pel_internal_send_dead_letter(
+                        event, "the loop has no handler for this event",
+                        stringify!($async_loop_name)),
+                },
+                Err(_) => {
+                    // Disconnected from main thread
+                    self._pel_internal_shut_down().await;
+                },
+            }
+        }
+
+        /// Returns false once the loop was shut down: its task then ends.
+        pub fn is_running(&self) -> bool {
+            self._pel_internal_running
+        }
+
+        /// Calls on\_start. Called by the task of the loop before it handles any event.
+        pub async fn start(&mut self) {
+            [<$async_loop_name EventHandlers>]::on_start(self).await;
+        }
+
+        async fn _pel_internal_shut_down(&mut self) {
+            if self._pel_internal_running {
+                self._pel_internal_running = false;
+                [<$async_loop_name EventHandlers>]::on_shutdown(self).await;
+            }
+        }
+
+        /// Exit the application: every loop is shut down once it handled its pending events.
+        pub fn exit(&self) -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self.exit_with(0, concat!(stringify!($async_loop_name), " exited"))
+        }
+
+        /// Exit the application like exit, with the code and reason returned by pel\_main.
+        ///
+        /// Only the first exit of the application is taken into account.
+        pub fn exit_with(&self, code: i32, reason: impl ::std::convert::Into<::std::string::String>)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publisher._pel_internal_event_sender.send(
+                PelAllEvents::PelInternalExitEvent($crate::PelExit::new(code, reason)))
+        }
+    }
+    )*)*
+
+    // ========================================================================================
+    //                              Main event loop
+    // ========================================================================================
+
+    /// Opens once every instance of a loop finished its on\_start hook, for each loop.
+    #[derive(::std::clone::Clone)]
+    pub struct PelStartLatches {
+        startup: $crate::PelStartup,
+        $($([<$active_loop_name:snake>]: $crate::PelLatch,)*)*
+        $($([<$reactive_loop_name:snake>]: $crate::PelLatch,)*)*
+    }
+
+    impl PelStartLatches {
+        /// Blocks until every loop is started.
+        fn wait_all(&self) {
+            $($(self.[<$active_loop_name:snake>].wait();)*)*
+            $($(self.[<$reactive_loop_name:snake>].wait();)*)*
+        }
+
+        fn are_all_open(&self) -> bool {
+            true $($(&& self.[<$active_loop_name:snake>].is_open())*)*
+                $($(&& self.[<$reactive_loop_name:snake>].is_open())*)*
+        }
+    }
+
+    // PelPoolSender::send or PelPoolSender::send_accepted
+    type PelSendFunction = fn(&$crate::PelPoolSender<PelAllEvents>, PelAllEvents, ::std::option::Option<u64>)
+        -> Result<(), $crate::PelSendError<PelAllEvents>>;
+
+    /// Holds the senders of the queues of every event loop.
+    ///
+    /// Owned by the main event loop: the queues of the loops are disconnected once it is
+    /// dropped. The publishers get uncounted copies, to send their events to their subscribers
+    /// themselves in direct delivery mode and to apply the backpressure of the queues.
+    #[derive(::std::clone::Clone)]
+    pub struct PelEventSenders {
+        $($(
+            [<_pel_internal_ $reactive_loop_name:snake _event_sender>]:
+                $crate::PelPoolSender<PelAllEvents>,
+            [<_pel_internal_ $reactive_loop_name:snake _subscriptions>]: [<$reactive_loop_name Subscriptions>],
+        )*)*
+        $($(
+            [<_pel_internal_ $active_loop_name:snake _event_sender>]:
+                $crate::PelPoolSender<PelAllEvents>,
+            [<_pel_internal_ $active_loop_name:snake _subscriptions>]: [<$active_loop_name Subscriptions>],
+        )*)*
+        $($(
+            [<_pel_internal_ $async_loop_name:snake _event_sender>]:
+                $crate::PelPoolSender<PelAllEvents>,
+            [<_pel_internal_ $async_loop_name:snake _subscriptions>]: [<$async_loop_name Subscriptions>],
+        )*)*
+    }
+
+    impl PelEventSenders {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(
+            $($(
+            [<$reactive_loop_name:snake _event_sender>]:
+                $crate::PelPoolSender<PelAllEvents>,
+            [<$reactive_loop_name:snake _subscriptions>]: [<$reactive_loop_name Subscriptions>],
+            )*)*
+            $($(
             [<$active_loop_name:snake _event_sender>]:
-                ::std::sync::mpsc::Sender<PelAllEvents>,
+                $crate::PelPoolSender<PelAllEvents>,
+            [<$active_loop_name:snake _subscriptions>]: [<$active_loop_name Subscriptions>],
+            )*)*
+            $($(
+            [<$async_loop_name:snake _event_sender>]:
+                $crate::PelPoolSender<PelAllEvents>,
+            [<$async_loop_name:snake _subscriptions>]: [<$async_loop_name Subscriptions>],
             )*)*
            ) -> Self {
-            PelMainEventLoop {
-                _pel_internal_event_receiver: event_receiver,
+            PelEventSenders {
            $($(
             [<_pel_internal_ $reactive_loop_name:snake _event_sender>]:
                 [<$reactive_loop_name:snake _event_sender>],
+            [<_pel_internal_ $reactive_loop_name:snake _subscriptions>]:
+                [<$reactive_loop_name:snake _subscriptions>],
             )*)*
            $($(
             [<_pel_internal_ $active_loop_name:snake _event_sender>]:
                 [<$active_loop_name:snake _event_sender>],
+            [<_pel_internal_ $active_loop_name:snake _subscriptions>]:
+                [<$active_loop_name:snake _subscriptions>],
+            )*)*
+           $($(
+            [<_pel_internal_ $async_loop_name:snake _event_sender>]:
+                [<$async_loop_name:snake _event_sender>],
+            [<_pel_internal_ $async_loop_name:snake _subscriptions>]:
+                [<$async_loop_name:snake _subscriptions>],
             )*)*
             }
         }
 
-        fn send_to_subscribed_event_senders(&self, event: &PelAllEvents) {
-            $($(if $reactive_loop_name::is_subscribed_to_event(&event) {
-                // An error means we have been disconnected.
-                // If it happens, it means the thread ended, therefore we don't have to notify it
-                // anyway
-                let _ = self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>]
-                    .send(event.clone());
+        /// Returns a copy which does not keep the queues connected, see PelSender::uncounted.
+        fn uncounted(&self) -> Self {
+            PelEventSenders {
+            $($(
+                [<_pel_internal_ $reactive_loop_name:snake _event_sender>]:
+                    self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>].uncounted(),
+                [<_pel_internal_ $reactive_loop_name:snake _subscriptions>]:
+                    self.[<_pel_internal_ $reactive_loop_name:snake _subscriptions>].clone(),
+            )*)*
+            $($(
+                [<_pel_internal_ $active_loop_name:snake _event_sender>]:
+                    self.[<_pel_internal_ $active_loop_name:snake _event_sender>].uncounted(),
+                [<_pel_internal_ $active_loop_name:snake _subscriptions>]:
+                    self.[<_pel_internal_ $active_loop_name:snake _subscriptions>].clone(),
+            )*)*
+            $($(
+                [<_pel_internal_ $async_loop_name:snake _event_sender>]:
+                    self.[<_pel_internal_ $async_loop_name:snake _event_sender>].uncounted(),
+                [<_pel_internal_ $async_loop_name:snake _subscriptions>]:
+                    self.[<_pel_internal_ $async_loop_name:snake _subscriptions>].clone(),
+            )*)*
+            }
+        }
+
+        /// Sends the event to every subscribed loop with the send function, PelPoolSender::send
+        /// or PelPoolSender::send\_accepted. For loops with several instances, the instance is
+        /// chosen by the balancing strategy of the loop.
+        ///
+        /// Subscribers share the event: the last one gets the event which was given, the others
+        /// get a new reference to it.
+        /// Loops whose thread ended are skipped. If a subscribed queue refuses the event, the
+        /// event is still sent to the other loops, a dead letter is sent for each refusal and the
+        /// first error is returned. An event no loop is subscribed to is dropped.
+        fn send_to_subscribed_event_senders(&self, event: PelAllEvents, send: PelSendFunction)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            let mut result = Ok(());
+            let mut is_subscribed = false;
+            // Sending is delayed by one subscriber so that the last one gets the event itself
+            let routing_key = event.routing_key();
+            let mut previous_sender: ::std::option::Option<
+                (&'static str, &$crate::PelPoolSender<PelAllEvents>)> = ::std::option::Option::None;
+            $($(if self.[<_pel_internal_ $reactive_loop_name:snake _subscriptions>].is_active(&event) {
+                is_subscribed = true;
+                if $reactive_loop_name::accepts_event(&event) {
+                    if let ::std::option::Option::Some((receiver, sender)) = previous_sender.replace(
+                        (stringify!($reactive_loop_name),
+                         &self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>])) {
+                        self.keep_first_error(&mut result, receiver,
+                                              send(sender, event.clone(), routing_key));
+                    }
+                }
             })*)*
-            $($(if $active_loop_name::is_subscribed_to_event(&event) {
-                // An error means we have been disconnected.
-                // If it happens, it means the thread ended, therefore we don't have to notify it
-                // anyway
-                let _ = self.[<_pel_internal_ $active_loop_name:snake _event_sender>]
-                    .send(event.clone());
+            $($(if self.[<_pel_internal_ $active_loop_name:snake _subscriptions>].is_active(&event) {
+                is_subscribed = true;
+                if $active_loop_name::accepts_event(&event) {
+                    if let ::std::option::Option::Some((receiver, sender)) = previous_sender.replace(
+                        (stringify!($active_loop_name),
+                         &self.[<_pel_internal_ $active_loop_name:snake _event_sender>])) {
+                        self.keep_first_error(&mut result, receiver,
+                                              send(sender, event.clone(), routing_key));
+                    }
+                }
             })*)*
+            $($(if self.[<_pel_internal_ $async_loop_name:snake _subscriptions>].is_active(&event) {
+                is_subscribed = true;
+                if $async_loop_name::accepts_event(&event) {
+                    if let ::std::option::Option::Some((receiver, sender)) = previous_sender.replace(
+                        (stringify!($async_loop_name),
+                         &self.[<_pel_internal_ $async_loop_name:snake _event_sender>])) {
+                        self.keep_first_error(&mut result, receiver,
+                                              send(sender, event.clone(), routing_key));
+                    }
+                }
+            })*)*
+            match previous_sender {
+                ::std::option::Option::Some((receiver, sender)) =>
+                    self.keep_first_error(&mut result, receiver, send(sender, event, routing_key)),
+                // Publishing events nobody listens to is allowed
+                ::std::option::Option::None => if !is_subscribed {
+                    ::log::debug!("No loop is subscribed to {}", event);
+                },
+            }
+            result
+        }
+
+        /// Waits for room in the queues of the subscribed loops whose backpressure policy is Block,
+        /// except the queue of the publishing loop, which would wait for itself. Returns the
+        /// subscribed loops whose queue is full and whose policy is Error.
+        fn wait_for_room(&self, event: &PelAllEvents, publisher: ::std::option::Option<&'static str>)
+            -> ::std::vec::Vec<&'static str> {
+            let routing_key = event.routing_key();
+            #[allow(unused_mut)]
+            let mut refusing_loops = ::std::vec::Vec::new();
+            $($(if publisher != ::std::option::Option::Some(stringify!($reactive_loop_name))
+                && self.[<_pel_internal_ $reactive_loop_name:snake _subscriptions>].is_active(event)
+                && $reactive_loop_name::accepts_event(event) {
+                if !self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>]
+                    .wait_for_room(routing_key) {
+                    refusing_loops.push(stringify!($reactive_loop_name));
+                }
+            })*)*
+            $($(if publisher != ::std::option::Option::Some(stringify!($active_loop_name))
+                && self.[<_pel_internal_ $active_loop_name:snake _subscriptions>].is_active(event)
+                && $active_loop_name::accepts_event(event) {
+                if !self.[<_pel_internal_ $active_loop_name:snake _event_sender>]
+                    .wait_for_room(routing_key) {
+                    refusing_loops.push(stringify!($active_loop_name));
+                }
+            })*)*
+            $($(if publisher != ::std::option::Option::Some(stringify!($async_loop_name))
+                && self.[<_pel_internal_ $async_loop_name:snake _subscriptions>].is_active(event)
+                && $async_loop_name::accepts_event(event) {
+                if !self.[<_pel_internal_ $async_loop_name:snake _event_sender>]
+                    .wait_for_room(routing_key) {
+                    refusing_loops.push(stringify!($async_loop_name));
+                }
+            })*)*
+            refusing_loops
+        }
+
+        /// Sends the control event to every instance of every loop, subscribed or not, even if
+        /// its queue is full.
+        fn send_to_every_event_sender(&self, event: PelAllEvents) {
+            $($(let _ = self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>]
+                .send_control_to_all(event.clone());)*)*
+            $($(let _ = self.[<_pel_internal_ $active_loop_name:snake _event_sender>]
+                .send_control_to_all(event.clone());)*)*
+            $($(let _ = self.[<_pel_internal_ $async_loop_name:snake _event_sender>]
+                .send_control_to_all(event.clone());)*)*
+        }
+
+        /// Sends the dead letter to every loop declared with `on dead letter`, or logs it if
+        /// there are none.
+        fn send_dead_letter(&self, dead_letter: $crate::PelDeadLetter<PelAllEvents>) {
+            #[allow(unused_mut)]
+            let mut n_sinks = 0;
+            $($($crate::__pel_if_present!(($($reactive_dead_letter_handler)?) {
+                n_sinks += 1;
+                if let Err(error) = self.[<_pel_internal_ $reactive_loop_name:snake _event_sender>]
+                    .send(PelAllEvents::PelInternalDeadLetterEvent(
+                        ::std::boxed::Box::new(dead_letter.clone())), ::std::option::Option::None) {
+                    ::log::error!("Dead letter not sent to {}: {}",
+                                  stringify!($reactive_loop_name), error.into_inner());
+                }
+            });)*)*
+            $($($crate::__pel_if_present!(($($active_dead_letter_handler)?) {
+                n_sinks += 1;
+                if let Err(error) = self.[<_pel_internal_ $active_loop_name:snake _event_sender>]
+                    .send(PelAllEvents::PelInternalDeadLetterEvent(
+                        ::std::boxed::Box::new(dead_letter.clone())), ::std::option::Option::None) {
+                    ::log::error!("Dead letter not sent to {}: {}",
+                                  stringify!($active_loop_name), error.into_inner());
+                }
+            });)*)*
+            if n_sinks == 0 {
+                ::log::warn!("Dead letter : {}", dead_letter);
+            }
+        }
+
+        fn keep_first_error(&self, result: &mut Result<(), $crate::PelSendError<PelAllEvents>>,
+                            receiver: &'static str,
+                            send_result: Result<(), $crate::PelSendError<PelAllEvents>>) {
+            match send_result {
+                // A disconnection means the thread ended, therefore we don't have to notify it
+                // anyway
+                Ok(()) | Err($crate::PelSendError::Disconnected(_)) => {},
+                Err($crate::PelSendError::Full(event)) => {
+                    self.send_dead_letter($crate::PelDeadLetter::new(
+                        event.clone(), "the queue of the loop is full", receiver));
+                    if result.is_ok() {
+                        *result = Err($crate::PelSendError::Full(event));
+                    }
+                },
+            }
+        }
+    }
+
+    pub struct PelMainEventLoop {
+        // Publishes the scheduled events once they are due
+        _pel_internal_publisher: PelPublisher,
+        _pel_internal_event_receiver: $crate::PelReceiver<PelAllEvents>,
+        _pel_internal_event_senders: PelEventSenders,
+        _pel_internal_timers: $crate::PelTimers<PelAllEvents>,
+        _pel_internal_running: ::std::sync::atomic::AtomicBool,
+        _pel_internal_exit: ::std::sync::Mutex<::std::option::Option<$crate::PelExit>>,
+        _pel_internal_start_latches: PelStartLatches,
+    }
+
+    impl PelMainEventLoop {
+        pub fn new(publisher: PelPublisher,
+                   event_receiver: $crate::PelReceiver<PelAllEvents>,
+                   event_senders: PelEventSenders,
+                   start_latches: PelStartLatches,
+           ) -> Self {
+            PelMainEventLoop {
+                // Its own queue must still be disconnected once every loop and publisher ended
+                _pel_internal_publisher: PelPublisher {
+                    _pel_internal_event_sender: publisher._pel_internal_event_sender.uncounted(),
+                    _pel_internal_source: ::std::option::Option::Some("PelMainEventLoop"),
+                    ..publisher
+                },
+                _pel_internal_event_receiver: event_receiver,
+                _pel_internal_event_senders: event_senders,
+                _pel_internal_timers: $crate::PelTimers::new(),
+                _pel_internal_running: ::std::sync::atomic::AtomicBool::new(true),
+                _pel_internal_exit: ::std::sync::Mutex::new(::std::option::Option::None),
+                _pel_internal_start_latches: start_latches,
+            }
         }
 
         /// Logs then send events to the subscribed event loops.
+        ///
+        /// In direct delivery mode, the event loops already sent the events to their
+        /// subscribers: they are only logged.
+        /// Scheduled events are held until their instant, then published by the main event loop
+        /// like by any publisher: it waits for room in the full queues whose policy is Block, and
+        /// a full queue whose policy is Error refuses the event, which becomes a dead letter. If
+        /// some are due, they are published without waiting for a new event.
         pub fn dispatch_events(&self) {
-            match self._pel_internal_event_receiver.recv() {
+            let expired_events = self._pel_internal_timers.take_expired(::std::time::Instant::now());
+            if !expired_events.is_empty() {
+                for event in expired_events {
+                    // Refusals are sent as dead letters by the publisher
+                    let _ = self._pel_internal_publisher._pel_internal_publish(event);
+                }
+                return;
+            }
+
+            let received = match self._pel_internal_timers.next_deadline() {
+                ::std::option::Option::None => self._pel_internal_event_receiver.recv()
+                    .map_err(|_| ::std::sync::mpsc::RecvTimeoutError::Disconnected),
+                ::std::option::Option::Some(deadline) =>
+                    self._pel_internal_event_receiver.recv_timeout(
+                        deadline.saturating_duration_since(::std::time::Instant::now())),
+            };
+            match received {
                 Ok(event) => {
                     ::log::info!("{}", event);
                     match event {
-                        $(PelAllEvents::$event_name([<$event_name:snake>]) => {
-                            self.send_to_subscribed_event_senders(
-                                &PelAllEvents::$event_name([<$event_name:snake>]));
-                        },)*
-                        PelInternalExitEvent => {
-                            ::std::process::exit(0);
+                        PelAllEvents::PelInternalExitEvent(exit) => self.shut_down(exit),
+                        PelAllEvents::PelInternalScheduledEvent(deadline, event, handle) => {
+                            self._pel_internal_timers.schedule(deadline, *event, handle);
+                        },
+                        PelAllEvents::PelInternalDeadLetterEvent(dead_letter) =>
+                            self._pel_internal_event_senders.send_dead_letter(*dead_letter),
+                        PelAllEvents::PelInternalExternalEvent(event) => {
+                            if self._pel_internal_publisher._pel_internal_delivery == $crate::PelDelivery::Hub {
+                                self.send_to_subscribed_event_senders(*event);
+                            }
+                        },
+                        event => {
+                            if self._pel_internal_publisher._pel_internal_delivery == $crate::PelDelivery::Hub {
+                                self.send_to_subscribed_event_senders(event);
+                            }
                         },
                     }
                 }
-                Err(::std::sync::mpsc::RecvError) => {
-                    // Disconnected
-                    ::std::process::exit(1);
+                Err(::std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    // A scheduled event is due: it is sent by the next call
+                },
+                Err(::std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    // Disconnected: no loop can publish anymore
+                    self.shut_down($crate::PelExit::new(
+                        1, "every event loop disconnected from the main event loop"));
+                },
+            }
+        }
+
+        /// With a startup barrier, blocks until every loop is started, so that the events
+        /// published at startup are held back in the queue until then. Returns at once otherwise.
+        ///
+        /// Called by pel\_run\_main\_loop\_indefinitely before it dispatches any event.
+        pub fn wait_for_startup(&self) {
+            if self._pel_internal_start_latches.startup == $crate::PelStartup::Barrier {
+                self._pel_internal_start_latches.wait_all();
+            }
+        }
+
+        /// Returns false once the main event loop was shut down: it then stops dispatching.
+        pub fn is_running(&self) -> bool {
+            self._pel_internal_running.load(::std::sync::atomic::Ordering::Relaxed)
+        }
+
+        /// Broadcasts a shutdown to every event loop. Scheduled events are dropped.
+        ///
+        /// Each loop handles the events already in its queue, calls its on\_shutdown hook and
+        /// ends. Only the first shutdown is taken into account.
+        pub fn shut_down(&self, exit: $crate::PelExit) {
+            if self._pel_internal_running.swap(false, ::std::sync::atomic::Ordering::Relaxed) {
+                if exit.is_success() {
+                    ::log::info!("Shutting down, {}", exit);
+                } else {
+                    ::log::error!("Shutting down, {}", exit);
+                }
+                *self._pel_internal_exit.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    ::std::option::Option::Some(exit);
+                self._pel_internal_event_senders.send_to_every_event_sender(
+                    PelAllEvents::PelInternalShutdownEvent);
+            }
+        }
+
+        /// Returns how the application exited, None while it is running.
+        pub fn exit_status(&self) -> ::std::option::Option<$crate::PelExit> {
+            self._pel_internal_exit.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+        }
+
+        // The publishers applied the backpressure of the queues: they must not block or refuse
+        // the main event loop
+        fn send_to_subscribed_event_senders(&self, event: PelAllEvents) {
+            // The events refused by a subscriber are sent as dead letters
+            let _ = self._pel_internal_event_senders.send_to_subscribed_event_senders(
+                event, $crate::PelPoolSender::send_accepted);
+        }
+    }
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Publishes events from outside the loops, for instance from callbacks or other threads.
+    /// It can be cloned and sent to any thread. Its events are logged as published by external.
+    ///
+    /// The main event loop keeps running while a publisher exists, even if every loop ended.
+    #[derive(::std::clone::Clone)]
+    pub struct PelPublisher {
+        _pel_internal_event_sender: $crate::PelSender<PelAllEvents>,
+        _pel_internal_event_senders: PelEventSenders,
+        _pel_internal_delivery: $crate::PelDelivery,
+        // Loop publishing the events, None for the publishers given to the application
+        _pel_internal_source: ::std::option::Option<&'static str>,
+    }
+
+    impl PelPublisher {
+        $(
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Errors are ignored: use the try\_publish variant to know if the event was refused.
+        pub fn [<publish_ $event_name:snake>](&self, [<$event_name:snake>]: $event_name) {
+            let _ = self.[<try_publish_ $event_name:snake>]([<$event_name:snake>]);
+        }
+
+        /// Sends the event to all threads which are subscribed.
+        ///
+        /// Fails if the main event loop ended or if the queue of a subscribed loop is full and
+        /// its backpressure policy is Error.
+        pub fn [<try_publish_ $event_name:snake>](&self, [<$event_name:snake>]: $event_name)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publish([<$event_name:snake>].into())
+        }
+        )*
+
+        // Sends the event to the main event loop or, in direct delivery mode, to the subscribed
+        // loops. Every loop publishes through this function.
+        fn _pel_internal_publish(&self, event: PelAllEvents)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            match self._pel_internal_delivery {
+                $crate::PelDelivery::Direct => {
+                    // In direct mode, the main loop only logs the event: don't bother
+                    // sending it if nothing will be logged
+                    if ::log::log_enabled!(::log::Level::Info) {
+                        let _ = self._pel_internal_event_sender.send(self.with_source(event.clone()));
+                    }
+                    self._pel_internal_event_senders.send_to_subscribed_event_senders(
+                        event, $crate::PelPoolSender::send)
+                },
+                $crate::PelDelivery::Hub => {
+                    // The backpressure of the subscribed queues applies to the publisher, not
+                    // to the main event loop. An event refused by a full queue is not sent at all.
+                    let refusing_loops = self._pel_internal_event_senders.wait_for_room(
+                        &event, self._pel_internal_source);
+                    if refusing_loops.is_empty() {
+                        return self._pel_internal_event_sender.send(self.with_source(event));
+                    }
+                    // Like in direct delivery mode, each refusal is a dead letter
+                    for receiver in refusing_loops {
+                        self._pel_internal_send_dead_letter(
+                            event.clone(), "the queue of the loop is full", receiver);
+                    }
+                    Err($crate::PelSendError::Full(event))
                 },
             }
         }
+
+        // Sends the event to the main event loop, which forwards it to the dead-letter sinks
+        fn _pel_internal_send_dead_letter(&self, event: PelAllEvents, reason: &str,
+                                          receiver: &'static str) {
+            let dead_letter = $crate::PelDeadLetter::new(event, reason, receiver);
+            let _ = self._pel_internal_event_sender.send(
+                PelAllEvents::PelInternalDeadLetterEvent(::std::boxed::Box::new(dead_letter)));
+        }
+
+        // The events of the publishers given to the application are logged as external
+        fn with_source(&self, event: PelAllEvents) -> PelAllEvents {
+            match self._pel_internal_source {
+                ::std::option::Option::Some(_) => event,
+                ::std::option::Option::None =>
+                    PelAllEvents::PelInternalExternalEvent(::std::boxed::Box::new(event)),
+            }
+        }
     }
 
     // ========================================================================================
@@ -374,26 +1975,48 @@ macro_rules! create_event_loops {
 
     /// Auto-generated by pel::create\_event\_loops! macro.
     ///
-    /// Holds every event loop that we can create
+    /// Holds every event loop that we can create.
+    /// Loops declared with several instances are held in a Vec.
     pub struct PelAllEventLoops {
-        $($(pub [<$active_loop_name:snake>]: $active_loop_name,)*)*
-        $($(pub [<$reactive_loop_name:snake>]: $reactive_loop_name,)*)*
+        $($(pub [<$active_loop_name:snake>]:
+            $crate::__pel_instances_type!($active_loop_name; $($active_instances)?),)*)*
+        $($(pub [<$reactive_loop_name:snake>]:
+            $crate::__pel_instances_type!($reactive_loop_name; $($reactive_instances)?),)*)*
+        $($(pub [<$async_loop_name:snake>]: $async_loop_name,)*)*
     }
 
     /// Auto-generated by pel::create\_event\_loops! macro.
     ///
     /// Creates the event loops and returns them in a big struct.
-    fn pel_create_event_loops() -> (PelMainEventLoop, PelAllEventLoops) {
+    $visibility fn pel_create_event_loops() -> (PelMainEventLoop, PelAllEventLoops) {
+        let (main_event_loop, all_event_loops, _) = pel_create_event_loops_with_publisher();
+        (main_event_loop, all_event_loops)
+    }
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Creates the event loops like pel\_create\_event\_loops, and a publisher to publish
+    /// events from outside them.
+    $visibility fn pel_create_event_loops_with_publisher()
+        -> (PelMainEventLoop, PelAllEventLoops, PelPublisher) {
+        // Loops starting after each other would wait for each other forever
+        $crate::pel_check_start_order(&[
+            $($((stringify!($active_loop_name),
+                 &[$($(stringify!($active_dependency)),*)?]),)*)*
+            $($((stringify!($reactive_loop_name),
+                 &[$($(stringify!($reactive_dependency)),*)?]),)*)*
+        ]);
+
         // Assert that every active loop implements the main loop trait
         $($([<_pel_assert_ $active_loop_name:snake _implements_its_main_loop_trait>]
             ::<$active_loop_name>();)*)*
 
         // Assert that every event handler trait is implemented by its event loop struct
-        $($($($([<_pel_assert_ $active_loop_name:snake _implements_its_event_handler_trait>]
-            ::<$active_loop_name>();
-            // This variable will do nothing. While the empty statement is optimized out by the
-            // compiler, this enables us to call the function only if there are events to react to.
-            let [<_pel_useless_ $event_to_react_to_active>] = 0;)*)*)*)*
+        $($($crate::__pel_if_present!(
+            ($($($event_to_react_to_active)*)* $($active_dead_letter_handler)?) {
+            [<_pel_assert_ $active_loop_name:snake _implements_its_event_handler_trait>]
+                ::<$active_loop_name>();
+        });)*)*
 
         $($($($([<_pel_assert_ $reactive_loop_name:snake _implements_its_event_handler_trait>]
             ::<$reactive_loop_name>();
@@ -402,89 +2025,369 @@ macro_rules! create_event_loops {
             let [<_pel_useless_ $event_to_react_to_reactive>] = 0;)*)*)*)*
 
         // Main event queue in which all events are sent
-        let (pel_main_event_sender, pel_main_event_receiver) = ::std::sync::mpsc::channel();
+        let (pel_main_event_sender, pel_main_event_receiver) = $crate::pel_channel(
+            "PelMainEventLoop",
+            ::std::option::Option::None,
+            $crate::PelBackpressure::Block,
+            PelAllEvents::priority);
 
-        // Create active event loops
+        // Create the event queues of every loop, one per instance
         $($(
-        // Reactive event queue in which all events are sent
-        let ([<pel_ $active_loop_name:snake _event_sender>],
-             [<pel_ $active_loop_name:snake _event_receiver>]) = ::std::sync::mpsc::channel();
-
-        let [<pel_ $active_loop_name:snake _struct>] = $active_loop_name::new(
-            pel_main_event_sender.clone(),
-            [<pel_ $active_loop_name:snake _event_receiver>],
-            $($init_field_active,)*
-            );
-
+        let ([<pel_ $active_loop_name:snake _event_senders>],
+             [<pel_ $active_loop_name:snake _event_receivers>]): (::std::vec::Vec<_>, ::std::vec::Vec<_>) =
+            (0..$crate::__pel_or!(1; $($active_instances)?)).map(|_| $crate::pel_channel(
+                stringify!($active_loop_name),
+                $crate::__pel_or_default!($(::std::option::Option::Some($active_capacity))?),
+                $crate::__pel_or_default!($($($crate::PelBackpressure::$active_backpressure)?)?),
+                PelAllEvents::priority)).unzip();
+        let [<pel_ $active_loop_name:snake _subscriptions>] = [<$active_loop_name Subscriptions>]::default();
+        let [<pel_ $active_loop_name:snake _event_sender>] = $crate::PelPoolSender::new(
+            [<pel_ $active_loop_name:snake _event_senders>],
+            $crate::__pel_or_default!($($($crate::PelBalance::$active_balance)?)?));
         )*)*
-
-        // Create reactive event loops
         $($(
-        // Reactive event queue in which all events are sent
-        let ([<pel_ $reactive_loop_name:snake _event_sender>],
-             [<pel_ $reactive_loop_name:snake _event_receiver>]) = ::std::sync::mpsc::channel();
-
-        let [<pel_ $reactive_loop_name:snake _struct>] = $reactive_loop_name::new(
-            pel_main_event_sender.clone(),
-            [<pel_ $reactive_loop_name:snake _event_receiver>],
-            $($init_field_reactive,)*
-            );
+        let ([<pel_ $reactive_loop_name:snake _event_senders>],
+             [<pel_ $reactive_loop_name:snake _event_receivers>]): (::std::vec::Vec<_>, ::std::vec::Vec<_>) =
+            (0..$crate::__pel_or!(1; $($reactive_instances)?)).map(|_| $crate::pel_channel(
+                stringify!($reactive_loop_name),
+                $crate::__pel_or_default!($(::std::option::Option::Some($reactive_capacity))?),
+                $crate::__pel_or_default!($($($crate::PelBackpressure::$reactive_backpressure)?)?),
+                PelAllEvents::priority)).unzip();
+        let [<pel_ $reactive_loop_name:snake _subscriptions>] = [<$reactive_loop_name Subscriptions>]::default();
+        let [<pel_ $reactive_loop_name:snake _event_sender>] = $crate::PelPoolSender::new(
+            [<pel_ $reactive_loop_name:snake _event_senders>],
+            $crate::__pel_or_default!($($($crate::PelBalance::$reactive_balance)?)?));
+        )*)*
 
+        $($(
+        let ([<pel_ $async_loop_name:snake _event_sender>],
+             [<pel_ $async_loop_name:snake _event_receiver>]) = $crate::pel_channel(
+                stringify!($async_loop_name),
+                $crate::__pel_or_default!($(::std::option::Option::Some($async_capacity))?),
+                $crate::__pel_or_default!($($($crate::PelBackpressure::$async_backpressure)?)?),
+                PelAllEvents::priority);
+        let [<pel_ $async_loop_name:snake _subscriptions>] = [<$async_loop_name Subscriptions>]::default();
         )*)*
 
-        let pel_main_event_loop = PelMainEventLoop::new(
-            pel_main_event_receiver,
+        let pel_event_senders = PelEventSenders::new(
             $($(
             [<pel_ $reactive_loop_name:snake _event_sender>],
+            [<pel_ $reactive_loop_name:snake _subscriptions>].clone(),
             )*)*
             $($(
             [<pel_ $active_loop_name:snake _event_sender>],
+            [<pel_ $active_loop_name:snake _subscriptions>].clone(),
+            )*)*
+            $($(
+            $crate::PelPoolSender::new(::std::vec![[<pel_ $async_loop_name:snake _event_sender>]],
+                                       $crate::PelBalance::RoundRobin),
+            [<pel_ $async_loop_name:snake _subscriptions>].clone(),
             )*)*
             );
 
+        let pel_delivery: $crate::PelDelivery =
+            $crate::__pel_or_default!($($crate::PelDelivery::$delivery)?);
+        // Every loop gets the senders of all loops: to send its events directly in direct
+        // delivery mode, and to apply the backpressure of their queues in both modes. Only the
+        // main event loop keeps their queues connected.
+        let pel_publisher = PelPublisher {
+            _pel_internal_event_sender: pel_main_event_sender,
+            _pel_internal_event_senders: pel_event_senders.uncounted(),
+            _pel_internal_delivery: pel_delivery,
+            _pel_internal_source: ::std::option::Option::None,
+        };
+
+        let pel_start_latches = PelStartLatches {
+            startup: $crate::__pel_or_default!($($crate::PelStartup::$startup)?),
+            $($([<$active_loop_name:snake>]: $crate::PelLatch::new(
+                $crate::__pel_or!(1; $($active_instances)?)),)*)*
+            $($([<$reactive_loop_name:snake>]: $crate::PelLatch::new(
+                $crate::__pel_or!(1; $($reactive_instances)?)),)*)*
+        };
+
+        // Create active event loops
+        $($(
+        let [<pel_ $active_loop_name:snake _instances>] =
+            [<pel_ $active_loop_name:snake _event_receivers>].into_iter().map(|event_receiver|
+                $active_loop_name::new(
+                    pel_publisher.clone(),
+                    event_receiver,
+                    [<pel_ $active_loop_name:snake _subscriptions>].clone(),
+                    pel_start_latches.clone(),
+                    $($init_field_active,)*
+                    ));
+        let [<pel_ $active_loop_name:snake _struct>] = $crate::__pel_instances!(
+            [<pel_ $active_loop_name:snake _instances>]; $($active_instances)?);
+        )*)*
+
+        // Create reactive event loops
+        $($(
+        let [<pel_ $reactive_loop_name:snake _instances>] =
+            [<pel_ $reactive_loop_name:snake _event_receivers>].into_iter().map(|event_receiver|
+                $reactive_loop_name::new(
+                    pel_publisher.clone(),
+                    event_receiver,
+                    [<pel_ $reactive_loop_name:snake _subscriptions>].clone(),
+                    pel_start_latches.clone(),
+                    $($init_field_reactive,)*
+                    ));
+        let [<pel_ $reactive_loop_name:snake _struct>] = $crate::__pel_instances!(
+            [<pel_ $reactive_loop_name:snake _instances>]; $($reactive_instances)?);
+        )*)*
+
+        // Create async event loops
+        $($(
+        let [<pel_ $async_loop_name:snake _struct>] = $async_loop_name::new(
+            pel_publisher.clone(),
+            [<pel_ $async_loop_name:snake _event_receiver>],
+            [<pel_ $async_loop_name:snake _subscriptions>].clone(),
+            $($init_field_async,)*
+            );
+        )*)*
+
+        let pel_main_event_loop = PelMainEventLoop::new(
+            pel_publisher.clone(),
+            pel_main_event_receiver,
+            pel_event_senders,
+            pel_start_latches,
+            );
+
         (pel_main_event_loop,
          PelAllEventLoops {
             $($([<$active_loop_name:snake>]: [<pel_ $active_loop_name:snake _struct>],)*)*
             $($([<$reactive_loop_name:snake>]: [<pel_ $reactive_loop_name:snake _struct>],)*)*
-        })
+            $($([<$async_loop_name:snake>]: [<pel_ $async_loop_name:snake _struct>],)*)*
+         },
+         pel_publisher)
     }
 
     /// Auto-generated by pel::create\_event\_loops! macro.
     ///
-    /// Launches every loop but the main in a separate thread.
-    fn pel_launch_event_loops_in_threads(all_event_loops: PelAllEventLoops) {
-        // Launch each active loop in a separate thread
+    /// Launches every loop but the main in a separate thread. Async loops are spawned on the
+    /// current tokio runtime: see pel\_launch\_event\_loops\_on\_runtime to give it explicitly.
+    $visibility fn pel_launch_event_loops_in_threads(all_event_loops: PelAllEventLoops) -> $crate::PelThreads {
+        pel_launch_event_loops(all_event_loops, ::std::option::Option::None)
+    }
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Launches every active loop in a separate thread, and schedules the reactive loops onto
+    /// n\_workers threads. Each reactive loop still handles its events one at a time, but the
+    /// loops share the workers: a handler which blocks holds its worker. The thread settings
+    /// of reactive loops are ignored.
+    ///
+    /// A handler publishing to a full queue whose policy is Block waits on its worker for the
+    /// consumer to make room, whatever the delivery mode. If the consumer needs this worker, for instance
+    /// with n\_workers = 1, the application deadlocks.
+    $visibility fn pel_launch_event_loops_in_workers(all_event_loops: PelAllEventLoops, n_workers: usize)
+        -> $crate::PelThreads {
+        pel_launch_event_loops(all_event_loops,
+                               ::std::option::Option::Some($crate::PelScheduler::new(n_workers)))
+    }
+
+    fn pel_launch_event_loops(all_event_loops: PelAllEventLoops,
+                              mut scheduler: ::std::option::Option<$crate::PelScheduler>)
+        -> $crate::PelThreads {
+        let mut threads = $crate::PelThreads::new();
+
+        // Launch each active loop in a separate thread, until it is shut down.
+        // Before each main loop iteration, the pending events are handled according to the
+        // drain setting of the loop.
         $($(
-        let mut [<$active_loop_name:snake _event_loop>] =
-            all_event_loops.[<$active_loop_name:snake>];
-        ::std::thread::spawn(move || loop {
-            [<$active_loop_name:snake _event_loop>].process_events();
-            [<$active_loop_name:snake _event_loop>].main_loop();
-        });)*)*
+        for mut [<$active_loop_name:snake _event_loop>] in $crate::__pel_instances_into_iter!(
+            all_event_loops.[<$active_loop_name:snake>]; $($active_instances)?) {
+        let config = $crate::__pel_thread_config!(stringify!($active_loop_name);
+            $($(named $active_thread_name)? $(stack $active_stack_size)?
+              $(pinned to ($($active_cpu),*))?
+              $(priority $active_priority($active_priority_value))?)?);
+        threads.spawn_with(config, move || {
+            [<$active_loop_name:snake _event_loop>].start();
+            while [<$active_loop_name:snake _event_loop>].is_running() {
+                [<$active_loop_name:snake _event_loop>].supervise(|event_loop| {
+                    event_loop.process_events();
+                    if event_loop.is_running() {
+                        event_loop.main_loop();
+                    }
+                });
+            }
+        });
+        })*)*
 
-        // Launch each reactive loop in a separate thread
+        // Launch each reactive loop in a separate thread or onto the workers, until it is shut
+        // down
         $($(
-        let mut [<$reactive_loop_name:snake _event_loop>] =
-            all_event_loops.[<$reactive_loop_name:snake>];
-        ::std::thread::spawn(move || loop {
-            [<$reactive_loop_name:snake _event_loop>].process_events();
-        });)*)*
+        for mut [<$reactive_loop_name:snake _event_loop>] in $crate::__pel_instances_into_iter!(
+            all_event_loops.[<$reactive_loop_name:snake>]; $($reactive_instances)?) {
+        if let ::std::option::Option::Some(scheduler) = &mut scheduler {
+            scheduler.add([<$reactive_loop_name:snake _event_loop>]);
+            continue;
+        }
+        let config = $crate::__pel_thread_config!(stringify!($reactive_loop_name);
+            $($(named $reactive_thread_name)? $(stack $reactive_stack_size)?
+              $(pinned to ($($reactive_cpu),*))?
+              $(priority $reactive_priority($reactive_priority_value))?)?);
+        threads.spawn_with(config, move || {
+            [<$reactive_loop_name:snake _event_loop>].start();
+            while [<$reactive_loop_name:snake _event_loop>].is_running() {
+                [<$reactive_loop_name:snake _event_loop>].supervise(|event_loop| {
+                    event_loop.process_events();
+                });
+            }
+        });
+        })*)*
+
+        if let ::std::option::Option::Some(scheduler) = scheduler {
+            scheduler.spawn(&mut threads);
+        }
+
+        // Spawn each async loop on the current tokio runtime, until it is shut down. Like the
+        // default supervision policy, a panic makes the application exit with code 101.
+        $($(
+        let mut [<$async_loop_name:snake _event_loop>] = all_event_loops.[<$async_loop_name:snake>];
+        let runtime = $crate::__pel_tokio::runtime::Handle::try_current().unwrap_or_else(|_| panic!(
+            concat!(stringify!($async_loop_name), " is an async loop: the event loops must be \
+                     launched from a tokio runtime, or with pel_launch_event_loops_on_runtime")));
+        let exit_sender = [<$async_loop_name:snake _event_loop>]._pel_internal_publisher._pel_internal_event_sender.clone();
+        let task = runtime.spawn(async move {
+            [<$async_loop_name:snake _event_loop>].start().await;
+            while [<$async_loop_name:snake _event_loop>].is_running() {
+                [<$async_loop_name:snake _event_loop>].process_events().await;
+            }
+        });
+        let end_signal = threads.track_task(stringify!($async_loop_name));
+        runtime.spawn(async move {
+            let _end_signal = end_signal;
+            if let Err(error) = task.await {
+                if error.is_panic() {
+                    let panic = error.into_panic();
+                    let message = $crate::pel_panic_message(&*panic);
+                    ::log::error!("{} panicked: {}", stringify!($async_loop_name), message);
+                    let _ = exit_sender.send(PelAllEvents::PelInternalExitEvent($crate::PelExit::new(
+                        101, ::std::format!("{} panicked: {}", stringify!($async_loop_name), message))));
+                }
+            }
+        });
+        )*)*
+
+        threads
     }
 
+    $crate::__pel_if_present!(($($($async_loop_name)*)?)
     /// Auto-generated by pel::create\_event\_loops! macro.
     ///
-    /// Starts only the main loop in the current thread.
+    /// Launches the loops like pel\_launch\_event\_loops\_in\_threads, spawning the async loops
+    /// on the given tokio runtime.
+    $visibility fn pel_launch_event_loops_on_runtime(all_event_loops: PelAllEventLoops,
+                                         runtime: &$crate::__pel_tokio::runtime::Handle)
+        -> $crate::PelThreads {
+        let _runtime_guard = runtime.enter();
+        pel_launch_event_loops_in_threads(all_event_loops)
+    });
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Starts only the main loop in the current thread, until it is shut down.
     /// If you are not testing the library, use pel_main instead.
-    fn pel_run_main_loop_indefinitely(main_event_loop: PelMainEventLoop) {
-        loop {
+    ///
+    /// Returns how the application exited.
+    $visibility fn pel_run_main_loop_indefinitely(main_event_loop: PelMainEventLoop) -> $crate::PelExit {
+        main_event_loop.wait_for_startup();
+        while main_event_loop.is_running() {
             main_event_loop.dispatch_events();
         }
+        main_event_loop.exit_status().unwrap_or_else(|| $crate::PelExit::new(0, "shut down"))
     }
 
     /// Auto-generated by pel::create\_event\_loops! macro.
     ///
-    /// Initializes log4rs.
-    fn pel_init_log4rs() {
+    /// Returned by pel\_spawn to control the event loops from the thread which spawned them.
+    ///
+    /// Dropping the handle detaches the threads: the loops run until one of them exits.
+    pub struct PelSystemHandle {
+        _pel_internal_publisher: PelPublisher,
+        _pel_internal_main_thread: ::std::thread::JoinHandle<$crate::PelExit>,
+        _pel_internal_threads: $crate::PelThreads,
+    }
+
+    impl PelSystemHandle {
+        /// Sends the event to all loops which are subscribed, like a PelPublisher.
+        ///
+        /// Fails once the main event loop ended or if the queue of a subscribed loop is full and
+        /// its backpressure policy is Error.
+        pub fn publish(&self, event: impl ::std::convert::Into<PelAllEvents>)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publisher._pel_internal_publish(event.into())
+        }
+
+        /// Returns a publisher which can be sent to other threads.
+        pub fn publisher(&self) -> PelPublisher {
+            self._pel_internal_publisher.clone()
+        }
+
+        /// Makes the application exit with code 0, like a loop calling exit.
+        pub fn shutdown(&self) -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self.shutdown_with(0, "shut down by the system handle")
+        }
+
+        /// Makes the application exit with the given code and reason, like a loop calling
+        /// exit\_with. Only the first exit is taken into account.
+        pub fn shutdown_with(&self, code: i32, reason: impl ::std::convert::Into<::std::string::String>)
+            -> Result<(), $crate::PelSendError<PelAllEvents>> {
+            self._pel_internal_publisher._pel_internal_event_sender.send(
+                PelAllEvents::PelInternalExitEvent($crate::PelExit::new(code, reason)))
+        }
+
+        /// Returns false once the main event loop stopped dispatching events.
+        pub fn is_running(&self) -> bool {
+            !self._pel_internal_main_thread.is_finished()
+        }
+
+        /// Returns the number of loop threads and tasks which have not ended yet.
+        pub fn n_running(&self) -> usize {
+            self._pel_internal_threads.n_running()
+        }
+
+        /// Waits for the application to exit, then for the threads of the loops like pel\_main.
+        /// Call shutdown first to make it exit.
+        ///
+        /// Returns how the application exited.
+        pub fn join(self) -> $crate::PelExit {
+            let exit = self._pel_internal_main_thread.join().unwrap_or_else(|panic| {
+                let message = $crate::pel_panic_message(&*panic);
+                ::log::error!("The main event loop panicked: {}", message);
+                $crate::PelExit::new(101, ::std::format!("the main event loop panicked: {}", message))
+            });
+            self._pel_internal_threads.join($crate::PEL_SHUTDOWN_GRACE_PERIOD);
+            exit
+        }
+    }
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Launches every event loop in a separate thread like pel\_main, and the main event loop in
+    /// one more thread, then returns at once. The calling thread stays free, for instance for a
+    /// GUI or an async runtime. Logging is not initialized: call pel\_init\_log4rs before if
+    /// needed.
+    $visibility fn pel_spawn() -> PelSystemHandle {
+        let (main_event_loop, all_event_loops, publisher) = pel_create_event_loops_with_publisher();
+        let threads = pel_launch_event_loops_in_threads(all_event_loops);
+        let main_thread = ::std::thread::Builder::new()
+            .name(::std::string::String::from("PelMainEventLoop"))
+            .spawn(move || pel_run_main_loop_indefinitely(main_event_loop))
+            .unwrap_or_else(|error| panic!("Failed to spawn the PelMainEventLoop thread: {}", error));
+        PelSystemHandle {
+            _pel_internal_publisher: publisher,
+            _pel_internal_main_thread: main_thread,
+            _pel_internal_threads: threads,
+        }
+    }
+
+    /// Auto-generated by pel::create\_event\_loops! macro.
+    ///
+    /// Initializes log4rs. If a logger was already initialized, for instance by another system,
+    /// it is kept: the log file of this system is not written to, and a warning saying so is
+    /// logged to the existing logger.
+    $visibility fn pel_init_log4rs() {
         $(
         let pattern = ::std::boxed::Box::new(
             ::log4rs::encode::pattern::PatternEncoder::new("[{d(%Y-%m-%d %H:%M:%S)}] {m}\n"));
@@ -514,7 +2417,11 @@ macro_rules! create_event_loops {
             .build(::log4rs::config::Root::builder().appender("stdout").appender("logfile")
                    .build(::log::LevelFilter::Trace))
             .unwrap();
-        let _handle = ::log4rs::init_config(config).unwrap();
+        // Another system of the application may already have initialized the logger
+        if let Err(error) = ::log4rs::init_config(config) {
+            ::log::warn!("The log configuration of {} was not applied, {} is not written to: {}",
+                         module_path!(), $log_file, error);
+        }
         )*
     }
 
@@ -522,17 +2429,279 @@ macro_rules! create_event_loops {
     ///
     /// Launches every event loop in a separate thread and runs a main event loop in the main
     /// thread.
-    fn pel_main() {
+    ///
+    /// Returns once a loop called exit and the threads of the event loops ended, or after
+    /// PEL\_SHUTDOWN\_GRACE\_PERIOD for the ones which did not. The code and reason given to
+    /// exit\_with are returned, and main can return them as the exit status of the process.
+    $visibility fn pel_main() -> $crate::PelExit {
         pel_init_log4rs();
 
         let (main_event_loop, all_event_loops) = pel_create_event_loops();
-        pel_launch_event_loops_in_threads(all_event_loops);
-        pel_run_main_loop_indefinitely(main_event_loop);
+        let threads = pel_launch_event_loops_in_threads(all_event_loops);
+        let exit = pel_run_main_loop_indefinitely(main_event_loop);
+        threads.join($crate::PEL_SHUTDOWN_GRACE_PERIOD);
+        exit
     }
 } // ::paste::paste
 } // Macro parameters
 } // macro_rules!
 
+/// Calls a subscription filter. The bound lets the compiler infer the type of the closure argument.
+#[doc(hidden)]
+pub fn pel_apply_filter<E, F: Fn(&E) -> bool>(event: &E, filter: F) -> bool {
+    filter(event)
+}
+
+/// Expands to the given value, or to the default value of the expected type if there is none.
+///
+/// Used by create\_event\_loops! for optional parameters.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_or_default {
+    () => {
+        ::std::default::Default::default()
+    };
+    ($value: expr) => {
+        $value
+    };
+}
+
+/// Expands to the supervision policy of a loop: `Restart(3 per 1m)` is given as
+/// `Restart 3 1m`, and the default is Escalate.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_supervision {
+    () => {
+        $crate::PelSupervision::Escalate
+    };
+    (Restart $max_restarts: literal $window: tt) => {
+        $crate::PelSupervision::RestartUpTo {
+            max_restarts: $max_restarts,
+            window: $crate::__pel_duration!($window),
+        }
+    };
+    ($supervision: ident) => {
+        $crate::PelSupervision::$supervision
+    };
+}
+
+/// Expands to the duration written in create\_event\_loops!, such as `500ms`. It is parsed in a
+/// const item, so that an invalid duration fails the build.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_duration {
+    ($duration: tt) => {{
+        const DURATION: ::std::time::Duration = $crate::pel_parse_duration(stringify!($duration));
+        DURATION
+    }};
+}
+
+/// Expands to the code if the first group is not empty, to nothing otherwise.
+///
+/// Used for optional clauses whose generated code does not use their value.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_if_present {
+    (() $($code: tt)*) => {};
+    (($($present: tt)+) $($code: tt)*) => {
+        $($code)*
+    };
+}
+
+/// Fails the build if async loops are declared without the async feature.
+#[cfg(feature = "async")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_require_async_feature {
+    () => {};
+}
+
+/// Fails the build if async loops are declared without the async feature.
+#[cfg(not(feature = "async"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_require_async_feature {
+    () => {
+        compile_error!("async loops require the async feature of pel");
+    };
+}
+
+/// Expands to the PelThreadConfig of a loop, named after the loop unless a name is given.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_thread_config {
+    ($default_name: expr; $(named $name: literal)? $(stack $stack_size: literal)?
+     $(pinned to ($($cpu: literal),*))? $(priority $priority: ident ($value: literal))?) => {
+        $crate::PelThreadConfig {
+            name: ::std::string::ToString::to_string($crate::__pel_or!($default_name; $($name)?)),
+            stack_size: $crate::__pel_or!(::std::option::Option::None;
+                $(::std::option::Option::Some($stack_size))?),
+            cpus: ::std::vec![$($($cpu),*)?],
+            priority: $crate::__pel_or!(::std::option::Option::None;
+                $(::std::option::Option::Some($crate::PelThreadPriority::$priority($value)))?),
+        }
+    };
+}
+
+/// Expands to the given value, or to the default value if there is none.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_or {
+    ($default: expr;) => {
+        $default
+    };
+    ($default: expr; $value: expr) => {
+        $value
+    };
+}
+
+/// Expands to the type holding the instances of a loop: the loop itself, or a Vec of loops if
+/// the loop was declared with a number of instances.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_instances_type {
+    ($event_loop: ty;) => {
+        $event_loop
+    };
+    ($event_loop: ty; $n_instances: literal) => {
+        ::std::vec::Vec<$event_loop>
+    };
+}
+
+/// Collects an iterator over the instances of a loop, see \_\_pel\_instances\_type.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_instances {
+    ($instances: expr;) => {{
+        let mut instances = $instances;
+        instances.next().unwrap()
+    }};
+    ($instances: expr; $n_instances: literal) => {
+        $instances.collect::<::std::vec::Vec<_>>()
+    };
+}
+
+/// Iterates over the instances of a loop, see \_\_pel\_instances\_type.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_instances_into_iter {
+    ($instances: expr;) => {
+        ::std::iter::once($instances)
+    };
+    ($instances: expr; $n_instances: literal) => {
+        $instances.into_iter()
+    };
+}
+
+/// Handles an attribute of an event field. The only attribute is #[key], which adds the field to
+/// the routing key of the event.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_event_field_attribute {
+    (key; $hasher: ident, $field: expr) => {
+        ::std::hash::Hash::hash(
+            $field,
+            $hasher.get_or_insert_with(::std::collections::hash_map::DefaultHasher::new),
+        )
+    };
+    ($attribute: ident; $hasher: ident, $field: expr) => {
+        compile_error!(concat!(
+            "Unknown event field attribute #[",
+            stringify!($attribute),
+            "], expected #[key]"
+        ))
+    };
+}
+
+/// Expands to the drain setting of an active loop: all (the default), one or a number of events.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_drain {
+    () => {
+        $crate::PelDrain::All
+    };
+    (all) => {
+        $crate::PelDrain::All
+    };
+    (one) => {
+        $crate::PelDrain::One
+    };
+    ($n_max_events: literal) => {
+        $crate::PelDrain::UpTo($n_max_events)
+    };
+}
+
+/// Expands to the result of the subscription filter applied to the event, true if there is none.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_filter {
+    ($event: expr;) => {
+        true
+    };
+    ($event: expr; $filter: expr) => {
+        $crate::pel_apply_filter(&**$event, $filter)
+    };
+}
+
+/// Expands to the type given to an event handler, depending on how the loop receives its events:
+/// by value (the default), by ref or by arc.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_handler_event_type {
+    (; $event: ty) => {
+        $event
+    };
+    (value; $event: ty) => {
+        $event
+    };
+    (ref; $event: ty) => {
+        &$event
+    };
+    (arc; $event: ty) => {
+        ::std::sync::Arc<$event>
+    };
+}
+
+/// Expands to the response type of an event: () if the event has no `-> Type` clause.
+///
+/// Complex types are written between parentheses, which are removed here.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_response_type {
+    () => {
+        ()
+    };
+    (()) => {
+        ()
+    };
+    (($response: ty)) => {
+        $response
+    };
+    ($response: ty) => {
+        $response
+    };
+}
+
+/// Converts a shared event into what the event handler expects, see \_\_pel\_handler\_event\_type.
+///
+/// Events received by value are only cloned if another loop still holds them.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pel_handler_event {
+    (; $event: expr) => {
+        ::std::sync::Arc::try_unwrap($event).unwrap_or_else(|event| (*event).clone())
+    };
+    (value; $event: expr) => {
+        ::std::sync::Arc::try_unwrap($event).unwrap_or_else(|event| (*event).clone())
+    };
+    (ref; $event: expr) => {
+        &*$event
+    };
+    (arc; $event: expr) => {
+        $event
+    };
+}
+
 /// A simple wrapper around a condvar, implemented for convenience.
 ///
 /// See the official rust doc on condvar.
@@ -564,3 +2733,9 @@ impl PelTestCondvar {
         self.cvar.notify_one();
     }
 }
+
+impl Default for PelTestCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
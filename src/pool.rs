@@ -0,0 +1,147 @@
+use crate::{PelSendError, PelSender};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How the events sent to a loop declared with several instances are spread over them.
+///
+/// Selected per loop with `instances N balanced by Strategy` in create\_event\_loops!.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelBalance {
+    /// Each instance gets an event in turn, whatever its routing key.
+    RoundRobin,
+    /// The instance with the fewest pending events gets the event, whatever its routing key.
+    LeastQueued,
+    /// Events with the same routing key always go to the same instance. Events without a
+    /// routing key are spread round-robin. This is the default, so that the events with a key
+    /// are handled in order unless another strategy is chosen.
+    #[default]
+    HashByKey,
+}
+
+/// Sends items to one of the queues of the instances of a loop.
+pub struct PelPoolSender<T> {
+    senders: Vec<PelSender<T>>,
+    balance: PelBalance,
+    next: AtomicUsize,
+}
+
+impl<T> PelPoolSender<T> {
+    /// There must be at least one sender.
+    pub fn new(senders: Vec<PelSender<T>>, balance: PelBalance) -> Self {
+        assert!(
+            !senders.is_empty(),
+            "A loop must have at least one instance"
+        );
+        PelPoolSender {
+            senders,
+            balance,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a pool sender which does not keep the queues connected, see
+    /// PelSender::uncounted.
+    pub fn uncounted(&self) -> Self {
+        PelPoolSender {
+            senders: self.senders.iter().map(PelSender::uncounted).collect(),
+            balance: self.balance,
+            next: AtomicUsize::new(self.next.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Returns the number of instances.
+    pub fn n_instances(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Sends the item to the instance chosen by the balancing strategy.
+    pub fn send(&self, item: T, routing_key: Option<u64>) -> Result<(), PelSendError<T>> {
+        self.senders[self.pick_instance(routing_key)].send(item)
+    }
+
+    /// Sends an item whose sender already waited for room with wait\_for\_room, see
+    /// PelSender::send\_accepted. Unless the routing key picks the instance, an instance with
+    /// room is preferred to the one chosen by the balancing strategy.
+    pub fn send_accepted(&self, item: T, routing_key: Option<u64>) -> Result<(), PelSendError<T>> {
+        let mut instance = self.pick_instance(routing_key);
+        if !self.is_keyed(routing_key) && self.senders[instance].is_full() {
+            let n_instances = self.senders.len();
+            if let Some(instance_with_room) = (1..n_instances)
+                .map(|offset| (instance + offset) % n_instances)
+                .find(|&other| !self.senders[other].is_full())
+            {
+                instance = instance_with_room;
+            }
+        }
+        self.senders[instance].send_accepted(item)
+    }
+
+    /// Waits for room in the instance an item with the routing key would be sent to, see
+    /// PelSender::wait\_for\_room. Without a key, any instance with room will do: if there is
+    /// none, waits for the least queued one.
+    pub fn wait_for_room(&self, routing_key: Option<u64>) -> bool {
+        if self.is_keyed(routing_key) {
+            return self.senders[self.pick_instance(routing_key)].wait_for_room();
+        }
+        self.senders.iter().any(|sender| !sender.is_full())
+            || self
+                .senders
+                .iter()
+                .min_by_key(|sender| sender.len())
+                .is_some_and(PelSender::wait_for_room)
+    }
+
+    /// Returns true if the routing key picks the instance an item is sent to.
+    fn is_keyed(&self, routing_key: Option<u64>) -> bool {
+        self.balance == PelBalance::HashByKey && routing_key.is_some()
+    }
+
+    /// Sends a copy of the control item to every instance, see PelSender::send\_control.
+    ///
+    /// Every instance is tried, and the first error is returned.
+    pub fn send_control_to_all(&self, item: T) -> Result<(), PelSendError<T>>
+    where
+        T: Clone,
+    {
+        let mut result = Ok(());
+        for sender in &self.senders {
+            let sent = sender.send_control(item.clone());
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+
+    fn pick_instance(&self, routing_key: Option<u64>) -> usize {
+        let n_instances = self.senders.len();
+        if n_instances == 1 {
+            return 0;
+        }
+
+        match (self.balance, routing_key) {
+            (PelBalance::HashByKey, Some(routing_key)) => {
+                (routing_key % n_instances as u64) as usize
+            }
+            (PelBalance::LeastQueued, _) => self
+                .senders
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, sender)| sender.len())
+                .map(|(instance, _)| instance)
+                .unwrap_or(0),
+            (PelBalance::RoundRobin, _) | (PelBalance::HashByKey, None) => {
+                self.next.fetch_add(1, Ordering::Relaxed) % n_instances
+            }
+        }
+    }
+}
+
+impl<T> Clone for PelPoolSender<T> {
+    fn clone(&self) -> Self {
+        PelPoolSender {
+            senders: self.senders.clone(),
+            balance: self.balance,
+            next: AtomicUsize::new(self.next.load(Ordering::Relaxed)),
+        }
+    }
+}
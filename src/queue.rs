@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// What happens when an event is sent to a full queue.
+///
+/// Selected per loop with `capacity N when full Policy` in create\_event\_loops!.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelBackpressure {
+    /// The sender waits until there is room in the queue. This is the default.
+    #[default]
+    Block,
+    /// The event being sent is dropped.
+    DropNewest,
+    /// The oldest event of the queue is dropped to make room for the new one.
+    DropOldest,
+    /// The event is not sent and the sender gets a PelSendError::Full.
+    Error,
+}
+
+/// Error returned when an event could not be sent. Gives the event back.
+pub enum PelSendError<T> {
+    /// The queue is full and its backpressure policy is PelBackpressure::Error.
+    Full(T),
+    /// The receiving end of the queue was dropped.
+    Disconnected(T),
+}
+
+impl<T> PelSendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            PelSendError::Full(item) | PelSendError::Disconnected(item) => item,
+        }
+    }
+}
+
+impl<T> fmt::Debug for PelSendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PelSendError::Full(_) => write!(f, "Full(..)"),
+            PelSendError::Disconnected(_) => write!(f, "Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for PelSendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PelSendError::Full(_) => write!(f, "sending on a full queue"),
+            PelSendError::Disconnected(_) => write!(f, "sending on a disconnected queue"),
+        }
+    }
+}
+
+impl<T> std::error::Error for PelSendError<T> {}
+
+/// Items are grouped by priority, each group being a FIFO queue.
+struct PelQueueState<T> {
+    items: BTreeMap<u8, VecDeque<T>>,
+    n_items: usize,
+    n_senders: usize,
+    receiver_alive: bool,
+    waker: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl<T> PelQueueState<T> {
+    fn push(&mut self, priority: u8, item: T) {
+        self.items.entry(priority).or_default().push_back(item);
+        self.n_items += 1;
+    }
+
+    /// Removes the oldest item with the highest priority.
+    fn pop_first(&mut self) -> Option<T> {
+        let mut entry = self.items.last_entry()?;
+        let item = entry.get_mut().pop_front();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        self.n_items -= 1;
+        item
+    }
+
+    /// Removes the oldest item with the lowest priority.
+    fn pop_last(&mut self) -> Option<T> {
+        let mut entry = self.items.first_entry()?;
+        let item = entry.get_mut().pop_front();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        self.n_items -= 1;
+        item
+    }
+}
+
+struct PelQueue<T> {
+    name: &'static str,
+    capacity: Option<usize>,
+    backpressure: PelBackpressure,
+    priority: fn(&T) -> u8,
+    state: Mutex<PelQueueState<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> PelQueue<T> {
+    fn lock(&self) -> MutexGuard<'_, PelQueueState<T>> {
+        // A panic while holding the lock cannot leave the queue in an invalid state
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn is_full(&self, state: &PelQueueState<T>) -> bool {
+        self.capacity
+            .is_some_and(|capacity| state.n_items >= capacity)
+    }
+}
+
+/// Creates a queue holding at most capacity events (unbounded if None). The capacity must be at
+/// least 1.
+///
+/// Works like std::sync::mpsc::channel, except that items with a higher priority are received
+/// first. Items with the same priority are received in the order they were sent.
+/// The name of the queue is only used for logging.
+pub fn pel_channel<T>(
+    name: &'static str,
+    capacity: Option<usize>,
+    backpressure: PelBackpressure,
+    priority: fn(&T) -> u8,
+) -> (PelSender<T>, PelReceiver<T>) {
+    assert!(
+        capacity != Some(0),
+        "The {} queue must have a capacity of at least 1",
+        name
+    );
+    let queue = Arc::new(PelQueue {
+        name,
+        capacity,
+        backpressure,
+        priority,
+        state: Mutex::new(PelQueueState {
+            items: BTreeMap::new(),
+            n_items: 0,
+            n_senders: 1,
+            receiver_alive: true,
+            waker: None,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        PelSender {
+            queue: queue.clone(),
+            is_counted: true,
+        },
+        PelReceiver { queue },
+    )
+}
+
+/// The sending half of a pel\_channel. Can be cloned to be used by several threads.
+pub struct PelSender<T> {
+    queue: Arc<PelQueue<T>>,
+    // Only counted senders keep the receiver connected
+    is_counted: bool,
+}
+
+impl<T> PelSender<T> {
+    /// Returns a sender which does not keep the receiver connected: once every other sender
+    /// is dropped, the receiver is disconnected, even though this one can still send.
+    ///
+    /// Lets the owner of the queue decide when it is disconnected, while others send to it.
+    pub fn uncounted(&self) -> Self {
+        PelSender {
+            queue: self.queue.clone(),
+            is_counted: false,
+        }
+    }
+
+    /// Returns the number of items waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.lock().n_items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the queue is bounded and holds as many items as its capacity.
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full(&self.queue.lock())
+    }
+
+    /// Waits until the queue has room for an item, if it is full and its backpressure policy is
+    /// Block. Returns false if it is full and its policy is Error.
+    ///
+    /// Lets a sender apply the policy of the queue before the item is actually sent, possibly by
+    /// another thread.
+    pub fn wait_for_room(&self) -> bool {
+        let queue = &self.queue;
+        let mut state = queue.lock();
+        match queue.backpressure {
+            PelBackpressure::Block => {
+                while queue.is_full(&state) && state.receiver_alive {
+                    state = queue
+                        .not_full
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+                true
+            }
+            PelBackpressure::Error => !queue.is_full(&state),
+            PelBackpressure::DropNewest | PelBackpressure::DropOldest => true,
+        }
+    }
+
+    /// Sends the item, applying the backpressure policy of the queue if it is full.
+    pub fn send(&self, item: T) -> Result<(), PelSendError<T>> {
+        let queue = &self.queue;
+        let mut state = queue.lock();
+        if !state.receiver_alive {
+            return Err(PelSendError::Disconnected(item));
+        }
+
+        if queue.is_full(&state) {
+            match queue.backpressure {
+                PelBackpressure::Block => {
+                    while queue.is_full(&state) && state.receiver_alive {
+                        state = queue
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                    if !state.receiver_alive {
+                        return Err(PelSendError::Disconnected(item));
+                    }
+                }
+                PelBackpressure::DropNewest => {
+                    ::log::warn!("{} queue is full, dropped the newest event", queue.name);
+                    return Ok(());
+                }
+                PelBackpressure::DropOldest => {
+                    ::log::warn!("{} queue is full, dropped the oldest event", queue.name);
+                    state.pop_last();
+                }
+                PelBackpressure::Error => {
+                    return Err(PelSendError::Full(item));
+                }
+            }
+        }
+
+        self.push(state, item);
+        Ok(())
+    }
+
+    /// Sends an item whose sender already applied the Block or Error policy of the queue, see
+    /// wait\_for\_room. It is queued even if the queue filled up since, which happens when
+    /// senders race for its last places, instead of waiting or being refused. DropNewest and
+    /// DropOldest still apply.
+    pub fn send_accepted(&self, item: T) -> Result<(), PelSendError<T>> {
+        let queue = &self.queue;
+        let mut state = queue.lock();
+        if !state.receiver_alive {
+            return Err(PelSendError::Disconnected(item));
+        }
+
+        if queue.is_full(&state) {
+            match queue.backpressure {
+                PelBackpressure::DropNewest => {
+                    ::log::warn!("{} queue is full, dropped the newest event", queue.name);
+                    return Ok(());
+                }
+                PelBackpressure::DropOldest => {
+                    ::log::warn!("{} queue is full, dropped the oldest event", queue.name);
+                    state.pop_last();
+                }
+                PelBackpressure::Block | PelBackpressure::Error => {}
+            }
+        }
+
+        self.push(state, item);
+        Ok(())
+    }
+
+    /// Sends a control item, such as a shutdown, whatever the capacity and the backpressure
+    /// policy of the queue: it is never refused, dropped or delayed, even by a full queue.
+    ///
+    /// It is still received after the items of the same priority sent before it, so that a
+    /// shutdown comes after the events already queued.
+    pub fn send_control(&self, item: T) -> Result<(), PelSendError<T>> {
+        let state = self.queue.lock();
+        if !state.receiver_alive {
+            return Err(PelSendError::Disconnected(item));
+        }
+
+        self.push(state, item);
+        Ok(())
+    }
+
+    /// Queues the item and wakes up the receiver. Takes the lock to release it before calling
+    /// the waker.
+    fn push(&self, mut state: MutexGuard<'_, PelQueueState<T>>, item: T) {
+        let queue = &self.queue;
+        state.push((queue.priority)(&item), item);
+        queue.not_empty.notify_one();
+        let waker = state.waker.clone();
+        drop(state);
+        if let Some(waker) = waker {
+            waker();
+        }
+    }
+}
+
+impl<T> Clone for PelSender<T> {
+    fn clone(&self) -> Self {
+        if self.is_counted {
+            self.queue.lock().n_senders += 1;
+        }
+        PelSender {
+            queue: self.queue.clone(),
+            is_counted: self.is_counted,
+        }
+    }
+}
+
+impl<T> Drop for PelSender<T> {
+    fn drop(&mut self) {
+        if !self.is_counted {
+            return;
+        }
+        let mut state = self.queue.lock();
+        state.n_senders -= 1;
+        if state.n_senders == 0 {
+            // Wake up the receiver so that it notices the disconnection
+            self.queue.not_empty.notify_all();
+            let waker = state.waker.take();
+            drop(state);
+            if let Some(waker) = waker {
+                waker();
+            }
+        }
+    }
+}
+
+/// The receiving half of a pel\_channel.
+pub struct PelReceiver<T> {
+    queue: Arc<PelQueue<T>>,
+}
+
+impl<T> PelReceiver<T> {
+    /// Takes the first item of the queue. The caller must hold the lock.
+    fn pop(&self, state: &mut PelQueueState<T>) -> Option<T> {
+        let item = state.pop_first();
+        if item.is_some() {
+            self.queue.not_full.notify_one();
+        }
+        item
+    }
+
+    /// Returns the number of items waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.lock().n_items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls waker whenever an item is sent, and once every sender is dropped.
+    ///
+    /// Lets a receiver which does not block on the queue know when to poll it again.
+    pub fn set_waker(&self, waker: impl Fn() + Send + Sync + 'static) {
+        self.queue.lock().waker = Some(Arc::new(waker));
+    }
+
+    /// Blocks until an item is available or every sender is dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.queue.lock();
+        loop {
+            if let Some(item) = self.pop(&mut state) {
+                return Ok(item);
+            }
+            if state.n_senders == 0 {
+                return Err(RecvError);
+            }
+            state = self
+                .queue
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Returns immediately, with an item if there is one.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.queue.lock();
+        match self.pop(&mut state) {
+            Some(item) => Ok(item),
+            None if state.n_senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Blocks until an item is available, every sender is dropped or the timeout expires.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.queue.lock();
+        loop {
+            if let Some(item) = self.pop(&mut state) {
+                return Ok(item);
+            }
+            if state.n_senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self
+                .queue
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+}
+
+impl<T> Drop for PelReceiver<T> {
+    fn drop(&mut self) {
+        let pending_items = {
+            let mut state = self.queue.lock();
+            state.receiver_alive = false;
+            state.n_items = 0;
+            state.waker = None;
+            std::mem::take(&mut state.items)
+        };
+        // Wake up the blocked senders so that they notice the disconnection
+        self.queue.not_full.notify_all();
+        // Nobody will receive the pending items: drop them now rather than with the last
+        // sender, outside of the lock
+        drop(pending_items);
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// Implemented by every event declared in create\_event\_loops!.
+pub trait PelEvent {
+    /// What the handlers of the event return: the type declared with `Event { .. } -> Type`,
+    /// () otherwise.
+    type Response;
+}
+
+/// Travels with an event and lets its handler answer the loop which sent a request.
+///
+/// Events which were published instead of requested have a responder which does nothing.
+pub struct PelResponder<R> {
+    sender: Option<Sender<R>>,
+}
+
+impl<R> PelResponder<R> {
+    /// A responder for an event which nobody waits for.
+    pub fn none() -> Self {
+        PelResponder { sender: None }
+    }
+
+    /// Creates a responder and the handle which receives its response.
+    pub fn new() -> (Self, PelResponse<R>) {
+        let (sender, receiver) = channel();
+        (
+            PelResponder {
+                sender: Some(sender),
+            },
+            PelResponse { receiver },
+        )
+    }
+
+    /// Sends the response to the requester, if any. Only the first response is received.
+    pub fn respond(&self, response: R) {
+        if let Some(sender) = &self.sender {
+            // An error means the requester stopped waiting
+            let _ = sender.send(response);
+        }
+    }
+}
+
+impl<R> Clone for PelResponder<R> {
+    fn clone(&self) -> Self {
+        PelResponder {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Error returned when waiting for a response failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PelResponseError {
+    /// No response arrived in time.
+    Timeout,
+    /// The request was dropped without being answered: no loop handles it, or it could not be
+    /// sent.
+    NoResponder,
+}
+
+impl fmt::Display for PelResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PelResponseError::Timeout => write!(f, "timed out waiting for a response"),
+            PelResponseError::NoResponder => write!(f, "the request was not answered"),
+        }
+    }
+}
+
+impl std::error::Error for PelResponseError {}
+
+/// Handle returned by the request functions, to wait for the response of the handler.
+pub struct PelResponse<R> {
+    receiver: Receiver<R>,
+}
+
+impl<R> PelResponse<R> {
+    /// Blocks until the response arrives.
+    pub fn wait(self) -> Result<R, PelResponseError> {
+        self.receiver
+            .recv()
+            .map_err(|_| PelResponseError::NoResponder)
+    }
+
+    /// Blocks until the response arrives or the timeout expires.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<R, PelResponseError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map_err(|error| match error {
+                RecvTimeoutError::Timeout => PelResponseError::Timeout,
+                RecvTimeoutError::Disconnected => PelResponseError::NoResponder,
+            })
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{pel_panic_message, PelThreads};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How many events a task handles before letting the other tasks of its worker run.
+pub(crate) const PEL_TASK_BATCH: usize = 32;
+
+/// How often a task waiting for other loops to start checks them.
+pub const PEL_START_POLL_PERIOD: Duration = Duration::from_millis(1);
+
+/// What a task has left to do after a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PelTaskStatus {
+    /// There is more work pending: the task runs again after the other ready tasks.
+    Ready,
+    /// Nothing to do until the task is woken up, or until the given instant at the latest.
+    Waiting(Option<Instant>),
+    /// The task ended and is never run again.
+    Done,
+}
+
+/// Work scheduled by a PelScheduler. Reactive event loops implement it.
+pub trait PelTask: Send {
+    /// Used to log the panics of the task.
+    fn name(&self) -> &'static str;
+
+    /// Called once before the first run. The task must call the waker whenever it has something
+    /// new to do.
+    fn set_waker(&mut self, waker: PelWaker);
+
+    /// Does what is pending, at most max\_events events, without blocking.
+    fn run(&mut self, max_events: usize) -> PelTaskStatus;
+}
+
+// States of a task slot
+const IDLE: u8 = 0;
+const SCHEDULED: u8 = 1;
+const RUNNING: u8 = 2;
+// Running, and woken up in the meantime: runs again once done
+const NOTIFIED: u8 = 3;
+const DONE: u8 = 4;
+
+struct PelTaskSlot {
+    state: AtomicU8,
+    // Only locked by the worker running the task: the state ensures there is at most one
+    task: Mutex<Option<Box<dyn PelTask>>>,
+}
+
+struct PelSchedulerState {
+    ready: VecDeque<usize>,
+    timers: BinaryHeap<Reverse<(Instant, usize)>>,
+    // Earliest timer of each task, to avoid piling up timers which are later than it
+    earliest_timers: Vec<Option<Instant>>,
+    n_tasks_left: usize,
+}
+
+struct PelSchedulerShared {
+    slots: Vec<PelTaskSlot>,
+    state: Mutex<PelSchedulerState>,
+    work: Condvar,
+}
+
+impl PelSchedulerShared {
+    fn lock(&self) -> MutexGuard<'_, PelSchedulerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn wake(&self, id: usize) {
+        let slot = &self.slots[id];
+        loop {
+            match slot.state.load(Ordering::Acquire) {
+                IDLE => {
+                    if slot
+                        .state
+                        .compare_exchange(IDLE, SCHEDULED, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                    {
+                        self.lock().ready.push_back(id);
+                        self.work.notify_one();
+                        return;
+                    }
+                }
+                RUNNING => {
+                    if slot
+                        .state
+                        .compare_exchange(RUNNING, NOTIFIED, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                    {
+                        return;
+                    }
+                }
+                // Already going to run, or ended
+                _ => return,
+            }
+        }
+    }
+
+    /// Waits for a ready task, waking up the tasks whose timer expired. Returns None once every
+    /// task is done.
+    fn next_ready_task(&self) -> Option<usize> {
+        let mut state = self.lock();
+        loop {
+            if state.n_tasks_left == 0 {
+                return None;
+            }
+            let now = Instant::now();
+            while let Some(&Reverse((deadline, id))) = state.timers.peek() {
+                if deadline > now {
+                    break;
+                }
+                state.timers.pop();
+                if state.earliest_timers[id] == Some(deadline) {
+                    state.earliest_timers[id] = None;
+                }
+                // Woken up with the lock held, hence inlined
+                let slot = &self.slots[id];
+                if slot
+                    .state
+                    .compare_exchange(IDLE, SCHEDULED, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    state.ready.push_back(id);
+                } else {
+                    let _ = slot.state.compare_exchange(
+                        RUNNING,
+                        NOTIFIED,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    );
+                }
+            }
+            if let Some(id) = state.ready.pop_front() {
+                return Some(id);
+            }
+            state = match state.timers.peek() {
+                Some(&Reverse((deadline, _))) => {
+                    self.work
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+                None => self
+                    .work
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+    }
+
+    /// Runs the task once, then schedules it according to what it has left to do.
+    fn run(&self, id: usize) {
+        let slot = &self.slots[id];
+        slot.state.store(RUNNING, Ordering::Release);
+        let mut guard = slot
+            .task
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let status = match guard.as_mut() {
+            Some(task) => panic::catch_unwind(AssertUnwindSafe(|| task.run(PEL_TASK_BATCH)))
+                .unwrap_or_else(|panic| {
+                    ::log::error!(
+                        "{} panicked outside of its handlers and was stopped: {}",
+                        task.name(),
+                        pel_panic_message(&*panic)
+                    );
+                    PelTaskStatus::Done
+                }),
+            None => PelTaskStatus::Done,
+        };
+
+        match status {
+            PelTaskStatus::Done => {
+                slot.state.store(DONE, Ordering::Release);
+                // The task is dropped before the last one is counted as done
+                drop(guard.take());
+                drop(guard);
+                let mut state = self.lock();
+                state.n_tasks_left -= 1;
+                if state.n_tasks_left == 0 {
+                    // Let every worker notice that there is nothing left to run
+                    self.work.notify_all();
+                }
+            }
+            PelTaskStatus::Ready => {
+                drop(guard);
+                slot.state.store(SCHEDULED, Ordering::Release);
+                self.lock().ready.push_back(id);
+                self.work.notify_one();
+            }
+            PelTaskStatus::Waiting(deadline) => {
+                drop(guard);
+                if let Some(deadline) = deadline {
+                    let mut state = self.lock();
+                    let is_earliest = match state.earliest_timers[id] {
+                        Some(earliest) => deadline < earliest,
+                        None => true,
+                    };
+                    if is_earliest {
+                        state.earliest_timers[id] = Some(deadline);
+                        state.timers.push(Reverse((deadline, id)));
+                        // A sleeping worker may be waiting for a later deadline
+                        self.work.notify_one();
+                    }
+                }
+                if slot
+                    .state
+                    .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+                    .is_err()
+                {
+                    // Woken up while running
+                    slot.state.store(SCHEDULED, Ordering::Release);
+                    self.lock().ready.push_back(id);
+                    self.work.notify_one();
+                }
+            }
+        }
+    }
+}
+
+/// Wakes up a task of a PelScheduler so that it runs again.
+#[derive(Clone)]
+pub struct PelWaker {
+    shared: Arc<PelSchedulerShared>,
+    id: usize,
+}
+
+impl PelWaker {
+    pub fn wake(&self) {
+        self.shared.wake(self.id);
+    }
+}
+
+/// Runs many tasks on a fixed number of worker threads.
+///
+/// A task runs on one worker at a time, therefore the events of a loop are still handled one
+/// after the other. A task which blocks holds its worker: the other tasks run on the others.
+pub struct PelScheduler {
+    n_workers: usize,
+    tasks: Vec<Box<dyn PelTask>>,
+}
+
+impl PelScheduler {
+    /// There must be at least one worker.
+    pub fn new(n_workers: usize) -> Self {
+        assert!(n_workers > 0, "A scheduler must have at least one worker");
+        PelScheduler {
+            n_workers,
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, task: impl PelTask + 'static) {
+        self.tasks.push(Box::new(task));
+    }
+
+    /// Spawns the workers, which end once every task is done.
+    ///
+    /// Every task runs once at start.
+    pub fn spawn(self, threads: &mut PelThreads) {
+        let n_tasks = self.tasks.len();
+        let shared = Arc::new(PelSchedulerShared {
+            slots: self
+                .tasks
+                .into_iter()
+                .map(|task| PelTaskSlot {
+                    state: AtomicU8::new(SCHEDULED),
+                    task: Mutex::new(Some(task)),
+                })
+                .collect(),
+            state: Mutex::new(PelSchedulerState {
+                ready: (0..n_tasks).collect(),
+                timers: BinaryHeap::new(),
+                earliest_timers: vec![None; n_tasks],
+                n_tasks_left: n_tasks,
+            }),
+            work: Condvar::new(),
+        });
+        for (id, slot) in shared.slots.iter().enumerate() {
+            if let Some(task) = slot
+                .task
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .as_mut()
+            {
+                task.set_waker(PelWaker {
+                    shared: shared.clone(),
+                    id,
+                });
+            }
+        }
+
+        for i in 0..self.n_workers {
+            let shared = shared.clone();
+            threads.spawn(&format!("pel-worker-{}", i), move || {
+                while let Some(id) = shared.next_ready_task() {
+                    shared.run(id);
+                }
+            });
+        }
+    }
+}
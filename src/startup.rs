@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// When the events published at startup are delivered.
+///
+/// Selected with the optional `startup:` section of create\_event\_loops!.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelStartup {
+    /// Events are delivered as soon as they are published. This is the default.
+    #[default]
+    Immediate,
+    /// Events are held back until every loop finished its on\_start hook.
+    Barrier,
+}
+
+struct PelLatchState {
+    count: AtomicUsize,
+    lock: Mutex<()>,
+    opened: Condvar,
+}
+
+/// Opens once it was counted down a given number of times. Can be cloned to be shared between
+/// threads.
+#[derive(Clone)]
+pub struct PelLatch {
+    state: Arc<PelLatchState>,
+}
+
+impl PelLatch {
+    pub fn new(count: usize) -> Self {
+        PelLatch {
+            state: Arc::new(PelLatchState {
+                count: AtomicUsize::new(count),
+                lock: Mutex::new(()),
+                opened: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn count_down(&self) {
+        let _guard = self
+            .state
+            .lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let count = self.state.count.load(Ordering::Acquire);
+        if count > 0 {
+            self.state.count.store(count - 1, Ordering::Release);
+            if count == 1 {
+                self.state.opened.notify_all();
+            }
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state.count.load(Ordering::Acquire) == 0
+    }
+
+    /// Blocks until the latch is open.
+    pub fn wait(&self) {
+        if self.is_open() {
+            return;
+        }
+        let mut guard = self
+            .state
+            .lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while !self.is_open() {
+            guard = self
+                .state
+                .opened
+                .wait(guard)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+}
+
+/// Panics if a loop starts after itself or if loops start after each other: their threads would
+/// wait for each other forever. Each loop is given with the loops it starts after.
+#[doc(hidden)]
+pub fn pel_check_start_order(dependencies: &[(&str, &[&str])]) {
+    fn visit<'a>(
+        name: &'a str,
+        dependencies: &[(&'a str, &[&'a str])],
+        path: &mut Vec<&'a str>,
+        checked: &mut Vec<&'a str>,
+    ) {
+        if let Some(start) = path.iter().position(|visited| *visited == name) {
+            if start == path.len() - 1 {
+                panic!("{} cannot start after itself", name);
+            }
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name);
+            panic!(
+                "The loops cannot start after each other: {}",
+                cycle.join(" starts after ")
+            );
+        }
+        if checked.contains(&name) {
+            return;
+        }
+        path.push(name);
+        for (_, after) in dependencies
+            .iter()
+            .filter(|(loop_name, _)| *loop_name == name)
+        {
+            for dependency in after.iter() {
+                visit(dependency, dependencies, path, checked);
+            }
+        }
+        path.pop();
+        checked.push(name);
+    }
+
+    let mut checked = Vec::new();
+    for (name, _) in dependencies {
+        visit(name, dependencies, &mut Vec::new(), &mut checked);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Whether a loop currently receives one of the events it subscribes to.
+///
+/// Shared by the instances of the loop and by whoever routes events to it, so that the
+/// generated subscribe\_ and unsubscribe\_ functions take effect for everyone. Loops are
+/// subscribed to every event of their `subscribes to` clause at start.
+#[derive(Clone, Debug)]
+pub struct PelSubscription {
+    active: Arc<AtomicBool>,
+}
+
+impl PelSubscription {
+    pub fn subscribe(&self) {
+        self.active.store(true, Ordering::Relaxed);
+    }
+
+    /// Events already queued for the loop are still handled.
+    pub fn unsubscribe(&self) {
+        self.active.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for PelSubscription {
+    fn default() -> Self {
+        PelSubscription {
+            active: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
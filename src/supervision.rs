@@ -0,0 +1,88 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// What happens when a handler or the main\_loop of an event loop panics.
+///
+/// Selected per loop with `on panic Policy` in create\_event\_loops!, for instance
+/// `on panic Restart` or `on panic Restart(3 per 1m)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PelSupervision {
+    /// The application exits with code 101, like a panicking main. This is the default.
+    #[default]
+    Escalate,
+    /// The fields of the loop are reset to their initial values and on\_start is called again.
+    Restart,
+    /// Like Restart, but escalates if the loop panicked more than max\_restarts times within the
+    /// window.
+    RestartUpTo {
+        max_restarts: usize,
+        window: Duration,
+    },
+    /// The loop goes on with its current state.
+    Ignore,
+}
+
+/// What an event loop does about a panic, decided by its PelSupervisor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PelSupervisorAction {
+    Escalate,
+    Restart,
+    Ignore,
+}
+
+/// Applies the supervision policy of an event loop instance, counting its restarts.
+#[derive(Clone, Debug)]
+pub struct PelSupervisor {
+    supervision: PelSupervision,
+    restarts: VecDeque<Instant>,
+}
+
+impl PelSupervisor {
+    pub fn new(supervision: PelSupervision) -> Self {
+        PelSupervisor {
+            supervision,
+            restarts: VecDeque::new(),
+        }
+    }
+
+    /// Returns what to do about a panic which just happened.
+    pub fn on_panic(&mut self) -> PelSupervisorAction {
+        match self.supervision {
+            PelSupervision::Escalate => PelSupervisorAction::Escalate,
+            PelSupervision::Restart => PelSupervisorAction::Restart,
+            PelSupervision::Ignore => PelSupervisorAction::Ignore,
+            PelSupervision::RestartUpTo {
+                max_restarts,
+                window,
+            } => {
+                let now = Instant::now();
+                while self
+                    .restarts
+                    .front()
+                    .is_some_and(|restart| now.duration_since(*restart) > window)
+                {
+                    self.restarts.pop_front();
+                }
+                if self.restarts.len() < max_restarts {
+                    self.restarts.push_back(now);
+                    PelSupervisorAction::Restart
+                } else {
+                    PelSupervisorAction::Escalate
+                }
+            }
+        }
+    }
+}
+
+/// Returns the message given to panic!, if it is a string.
+#[doc(hidden)]
+pub fn pel_panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic payload"
+    }
+}
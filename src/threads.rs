@@ -0,0 +1,291 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long pel\_main waits for the threads of the event loops once the shutdown is broadcast.
+///
+/// Active loops blocked in their main\_loop, for instance on a read, cannot notice the shutdown:
+/// they are left running when pel\_main returns.
+pub const PEL_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Scheduling priority of a thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PelThreadPriority {
+    /// Nice value, from -20 (highest priority) to 19. Lowering it requires privileges.
+    Nice(i32),
+    /// Real-time SCHED\_FIFO priority, from 1 to 99. Requires privileges.
+    Fifo(i32),
+}
+
+/// How the thread of an event loop is spawned.
+///
+/// Selected per loop with `thread named "name" stack N pinned to (cpu, ...) priority P(value)` in
+/// create\_event\_loops!, each part being optional. CPU affinity and priority are only supported
+/// on Linux: elsewhere, or if the thread lacks the privileges, they are logged and ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PelThreadConfig {
+    /// Shown by top -H, perf or gdb. Linux only keeps its first 15 bytes.
+    pub name: String,
+    /// Stack size in bytes, the default of the standard library if None.
+    pub stack_size: Option<usize>,
+    /// CPUs the thread runs on, any CPU if empty.
+    pub cpus: Vec<usize>,
+    pub priority: Option<PelThreadPriority>,
+}
+
+impl PelThreadConfig {
+    /// The default configuration of a thread with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        PelThreadConfig {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Applies the CPU affinity and the priority to the current thread.
+    fn apply_to_current_thread(&self) {
+        if !self.cpus.is_empty() {
+            if let Err(error) = set_cpu_affinity(&self.cpus) {
+                ::log::warn!(
+                    "Failed to pin the {} thread to the CPUs {:?}: {}",
+                    self.name,
+                    self.cpus,
+                    error
+                );
+            }
+        }
+        if let Some(priority) = self.priority {
+            if let Err(error) = set_priority(priority) {
+                ::log::warn!(
+                    "Failed to set the priority of the {} thread to {:?}: {}",
+                    self.name,
+                    priority,
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(cpus: &[usize]) -> io::Result<()> {
+    // Safety: cpu_set_t is a plain bit mask and every CPU is checked to be in it
+    unsafe {
+        let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("there is no CPU {}", cpu),
+                ));
+            }
+            libc::CPU_SET(cpu, &mut cpu_set);
+        }
+        // 0 is the calling thread
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set) == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_priority(priority: PelThreadPriority) -> io::Result<()> {
+    match priority {
+        // On Linux, the nice value of PRIO_PROCESS 0 is the one of the calling thread
+        PelThreadPriority::Nice(nice) => {
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        }
+        PelThreadPriority::Fifo(priority) => {
+            let param = libc::sched_param {
+                sched_priority: priority,
+            };
+            match unsafe {
+                libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+            } {
+                0 => Ok(()),
+                error => Err(io::Error::from_raw_os_error(error)),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU affinity is only supported on Linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_priority(_priority: PelThreadPriority) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread priorities are only supported on Linux",
+    ))
+}
+
+/// The threads running the event loops.
+#[derive(Default)]
+pub struct PelThreads {
+    threads_and_tasks: Vec<PelTracked>,
+    ends: Arc<PelEnds>,
+}
+
+/// A thread, or a task running elsewhere, for instance on an async runtime.
+struct PelTracked {
+    name: String,
+    /// None for a task.
+    handle: Option<JoinHandle<()>>,
+    has_ended: Arc<AtomicBool>,
+}
+
+/// Counts the threads and tasks which ended, waking up join.
+#[derive(Default)]
+struct PelEnds {
+    n_ended: Mutex<usize>,
+    condvar: Condvar,
+}
+
+/// Held by a thread or task: signals its end when dropped, including by a panic or when the
+/// task is cancelled.
+pub struct PelEndSignal {
+    ends: Arc<PelEnds>,
+    has_ended: Arc<AtomicBool>,
+}
+
+impl Drop for PelEndSignal {
+    fn drop(&mut self) {
+        self.has_ended.store(true, Ordering::SeqCst);
+        let mut n_ended = self
+            .ends
+            .n_ended
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        *n_ended += 1;
+        self.ends.condvar.notify_all();
+    }
+}
+
+impl PelThreads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a thread with the given name.
+    pub fn spawn<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.spawn_with(PelThreadConfig::new(name), f);
+    }
+
+    /// Spawns a thread with the given configuration.
+    pub fn spawn_with<F>(&mut self, config: PelThreadConfig, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut builder = thread::Builder::new().name(config.name.clone());
+        if let Some(stack_size) = config.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let name = config.name.clone();
+        let end_signal = self.new_end_signal();
+        let has_ended = end_signal.has_ended.clone();
+        let handle = builder
+            .spawn(move || {
+                let _end_signal = end_signal;
+                config.apply_to_current_thread();
+                f()
+            })
+            .unwrap_or_else(|error| panic!("Failed to spawn the {} thread: {}", name, error));
+        self.threads_and_tasks.push(PelTracked {
+            name,
+            handle: Some(handle),
+            has_ended,
+        });
+    }
+
+    /// Tracks a task running elsewhere, so that join waits for it like for the threads. The
+    /// task must hold the returned signal until it ends.
+    pub fn track_task(&mut self, name: &str) -> PelEndSignal {
+        let end_signal = self.new_end_signal();
+        self.threads_and_tasks.push(PelTracked {
+            name: name.to_string(),
+            handle: None,
+            has_ended: end_signal.has_ended.clone(),
+        });
+        end_signal
+    }
+
+    fn new_end_signal(&self) -> PelEndSignal {
+        PelEndSignal {
+            ends: self.ends.clone(),
+            has_ended: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns the number of threads and tasks which have not ended yet.
+    pub fn n_running(&self) -> usize {
+        self.threads_and_tasks
+            .iter()
+            .filter(|tracked| !tracked.has_ended.load(Ordering::SeqCst))
+            .count()
+    }
+
+    /// Waits for every thread and task to end, at most for the grace period, and joins the
+    /// threads. Threads still running after it are detached and logged, like tasks.
+    ///
+    /// Returns the number of threads and tasks left running.
+    pub fn join(self, grace_period: Duration) -> usize {
+        let deadline = Instant::now() + grace_period;
+        {
+            let mut n_ended = self
+                .ends
+                .n_ended
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            while *n_ended < self.threads_and_tasks.len() {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                n_ended = self
+                    .ends
+                    .condvar
+                    .wait_timeout(n_ended, deadline - now)
+                    .unwrap_or_else(|error| error.into_inner())
+                    .0;
+            }
+        }
+
+        let mut n_running = 0;
+        for tracked in self.threads_and_tasks {
+            let kind = if tracked.handle.is_some() {
+                "thread"
+            } else {
+                "task"
+            };
+            if !tracked.has_ended.load(Ordering::SeqCst) {
+                ::log::warn!(
+                    "The {} {} did not end in time after the shutdown",
+                    tracked.name,
+                    kind
+                );
+                n_running += 1;
+            } else if let Some(handle) = tracked.handle {
+                // A panic was already reported by the thread itself
+                let _ = handle.join();
+            }
+        }
+        n_running
+    }
+}
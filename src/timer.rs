@@ -0,0 +1,267 @@
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Returned by the publish\_{event}\_after and publish\_{event}\_at functions, to cancel the
+/// publication of the event.
+#[derive(Clone, Debug, Default)]
+pub struct PelTimerHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PelTimerHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The event will not be published, unless it already was.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+struct PelTimer<T> {
+    deadline: Instant,
+    // Timers with the same deadline expire in the order they were scheduled
+    sequence: u64,
+    item: T,
+    handle: PelTimerHandle,
+}
+
+impl<T> PelTimer<T> {
+    fn key(&self) -> (Instant, u64) {
+        (self.deadline, self.sequence)
+    }
+}
+
+impl<T> PartialEq for PelTimer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<T> Eq for PelTimer<T> {}
+
+impl<T> PartialOrd for PelTimer<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for PelTimer<T> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key().cmp(&other.key())
+    }
+}
+
+struct PelTimersState<T> {
+    heap: BinaryHeap<Reverse<PelTimer<T>>>,
+    n_scheduled: u64,
+}
+
+/// Items waiting for their deadline, held by the main event loop.
+pub struct PelTimers<T> {
+    state: Mutex<PelTimersState<T>>,
+}
+
+impl<T> PelTimers<T> {
+    pub fn new() -> Self {
+        PelTimers {
+            state: Mutex::new(PelTimersState {
+                heap: BinaryHeap::new(),
+                n_scheduled: 0,
+            }),
+        }
+    }
+
+    pub fn schedule(&self, deadline: Instant, item: T, handle: PelTimerHandle) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let sequence = state.n_scheduled;
+        state.n_scheduled += 1;
+        state.heap.push(Reverse(PelTimer {
+            deadline,
+            sequence,
+            item,
+            handle,
+        }));
+    }
+
+    /// Returns the earliest deadline, None if no item is waiting.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.heap.peek().map(|timer| timer.0.deadline)
+    }
+
+    /// Removes the items whose deadline is reached, in the order of their deadlines. Cancelled
+    /// items are dropped.
+    pub fn take_expired(&self, now: Instant) -> Vec<T> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut expired = Vec::new();
+        while state
+            .heap
+            .peek()
+            .is_some_and(|timer| timer.0.deadline <= now)
+        {
+            if let Some(Reverse(timer)) = state.heap.pop() {
+                if !timer.handle.is_cancelled() {
+                    expired.push(timer.item);
+                }
+            }
+        }
+        expired
+    }
+}
+
+impl<T> Default for PelTimers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tells a loop when its periodic tick is due.
+///
+/// Ticks are due at fixed intervals from the creation of the ticker, so that the time taken to
+/// handle them does not delay the next ones. Ticks missed because the loop was busy are skipped.
+#[derive(Clone, Debug)]
+pub struct PelTicker {
+    period: Duration,
+    next_tick: Instant,
+}
+
+impl PelTicker {
+    /// The period must not be zero.
+    pub fn new(period: Duration) -> Self {
+        assert!(!period.is_zero(), "The period of a tick must not be zero");
+        PelTicker {
+            period,
+            next_tick: Instant::now() + period,
+        }
+    }
+
+    pub fn next_tick(&self) -> Instant {
+        self.next_tick
+    }
+
+    /// Returns true if a tick is due, and schedules the next one.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if now < self.next_tick {
+            return false;
+        }
+
+        let n_periods = (now - self.next_tick).as_nanos() / self.period.as_nanos() + 1;
+        self.next_tick += Duration::from_nanos((self.period.as_nanos() * n_periods) as u64);
+        true
+    }
+}
+
+/// Tells a loop when it has been idle, without any event, for too long.
+///
+/// Once idle, the loop is told again each time the timeout elapses, until it gets an event.
+#[derive(Clone, Debug)]
+pub struct PelIdleTimer {
+    timeout: Duration,
+    last_event: Instant,
+    deadline: Instant,
+}
+
+impl PelIdleTimer {
+    /// The timeout must not be zero.
+    pub fn new(timeout: Duration) -> Self {
+        assert!(!timeout.is_zero(), "The idle timeout must not be zero");
+        let now = Instant::now();
+        PelIdleTimer {
+            timeout,
+            last_event: now,
+            deadline: now + timeout,
+        }
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// To be called when the loop gets an event.
+    pub fn reset(&mut self, now: Instant) {
+        self.last_event = now;
+        self.deadline = now + self.timeout;
+    }
+
+    /// Returns for how long the loop has been idle if the timeout elapsed.
+    pub fn poll(&mut self, now: Instant) -> Option<Duration> {
+        if now < self.deadline {
+            return None;
+        }
+
+        self.deadline = now + self.timeout;
+        Some(now - self.last_event)
+    }
+}
+
+/// Parses the durations written in create\_event\_loops!, such as `1s`, `500ms` or `1.5m`.
+///
+/// The units are ns, us, ms, s, m and h. Panics if the duration is invalid or zero: evaluated
+/// in a const item by the generated code, this fails the build.
+#[doc(hidden)]
+pub const fn pel_parse_duration(text: &str) -> Duration {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    let mut value: u128 = 0;
+    let mut n_digits = 0;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        value = value * 10 + (bytes[i] - b'0') as u128;
+        n_digits += 1;
+        i += 1;
+    }
+    // Fractional digits are kept apart and divided by their scale once the unit is known
+    let mut fraction: u128 = 0;
+    let mut fraction_scale: u128 = 1;
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            fraction = fraction * 10 + (bytes[i] - b'0') as u128;
+            fraction_scale *= 10;
+            n_digits += 1;
+            i += 1;
+        }
+    }
+    if n_digits == 0 {
+        panic!("Invalid duration: it must start with a number, like 500ms");
+    }
+    // Keeps the number of seconds within a u64
+    if n_digits > 15 {
+        panic!("Invalid duration: it must have at most 15 digits");
+    }
+
+    let n_nanos_per_unit: u128 = match bytes.len() - i {
+        1 if bytes[i] == b's' => 1_000_000_000,
+        1 if bytes[i] == b'm' => 60_000_000_000,
+        1 if bytes[i] == b'h' => 3_600_000_000_000,
+        2 if bytes[i + 1] == b's' && bytes[i] == b'n' => 1,
+        2 if bytes[i + 1] == b's' && bytes[i] == b'u' => 1_000,
+        2 if bytes[i + 1] == b's' && bytes[i] == b'm' => 1_000_000,
+        _ => panic!("Invalid duration: the unit must be ns, us, ms, s, m or h"),
+    };
+    let n_nanos = value * n_nanos_per_unit + fraction * n_nanos_per_unit / fraction_scale;
+    if n_nanos == 0 {
+        panic!("Invalid duration: it must not be zero");
+    }
+    Duration::new(
+        (n_nanos / 1_000_000_000) as u64,
+        (n_nanos % 1_000_000_000) as u32,
+    )
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "async")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static N_QUERIES: AtomicUsize = AtomicUsize::new(0);
+static ANSWERS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pel::create_event_loops!(
+    events: Query {id: u32} -> String
+
+    active loops:
+        Client {} publishes (Query)
+
+    async loops:
+        Server {n_queries: usize = 0} subscribes to (Query by ref)
+);
+
+impl MainLoop for Client {
+    fn main_loop(&mut self) {
+        let responses = (0..3)
+            .map(|id| self.request_query(Query::new(id)))
+            .collect::<Vec<_>>();
+        for response in responses {
+            let answer = response.wait_timeout(Duration::from_secs(5)).unwrap();
+            ANSWERS.lock().unwrap().push(answer);
+        }
+        let _ = self.exit();
+    }
+}
+
+impl ServerEventHandlers for Server {
+    async fn on_query(&mut self, event: &Query) -> String {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        self.n_queries += 1;
+        format!("answer {}", event.id)
+    }
+
+    async fn on_shutdown(&mut self) {
+        N_QUERIES.store(self.n_queries, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_async_handlers_run_on_the_runtime() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let threads = pel_launch_event_loops_on_runtime(all_event_loops, runtime.handle());
+    let exit = pel_run_main_loop_indefinitely(main_event_loop);
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+
+    assert_eq!(exit.code, 0);
+    assert_eq!(
+        *ANSWERS.lock().unwrap(),
+        vec!["answer 0", "answer 1", "answer 2"]
+    );
+    assert_eq!(N_QUERIES.load(Ordering::Relaxed), 3);
+}
@@ -0,0 +1,75 @@
+use pel::PelDeadLetter;
+
+pel::create_event_loops!(
+    events: Job {id: u32},
+            Orphan {}
+
+    active loops:
+        Producer {} publishes (Job, Orphan)
+
+    reactive loops:
+        Worker {} subscribes to (Job) capacity 1 when full Error,
+
+        Auditor {letters: Vec<(String, &'static str, String)> = Vec::new()}
+            on dead letter => on_dead_letter
+);
+
+impl MainLoop for Producer {
+    fn main_loop(&mut self) {}
+}
+
+impl WorkerEventHandlers for Worker {
+    fn on_job(&mut self, _event: Job) {}
+}
+
+impl AuditorEventHandlers for Auditor {
+    fn on_dead_letter(&mut self, dead_letter: PelDeadLetter<PelAllEvents>) {
+        self.letters.push((
+            dead_letter.event.to_string(),
+            dead_letter.receiver,
+            dead_letter.reason,
+        ));
+    }
+}
+
+#[test]
+fn test_events_without_subscriber_are_not_dead_letters() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    all_event_loops.producer.publish_orphan(Orphan::new());
+    main_event_loop.dispatch_events();
+
+    assert!(!all_event_loops.auditor.try_process_events());
+    assert!(all_event_loops.auditor.letters.is_empty());
+}
+
+#[test]
+fn test_events_refused_by_a_full_queue_are_dead_letters() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    all_event_loops.producer.publish_job(Job::new(1));
+    main_event_loop.dispatch_events();
+    // The main event loop gets the dead letter instead of the refused event
+    all_event_loops.producer.publish_job(Job::new(2));
+    main_event_loop.dispatch_events();
+    all_event_loops.auditor.process_events();
+
+    assert_eq!(
+        all_event_loops.auditor.letters,
+        vec![(
+            "Job : id = 2, ".to_string(),
+            "Worker",
+            "the queue of the loop is full".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_dead_letter_display() {
+    let dead_letter = PelDeadLetter::new("Job 3", "the queue of the loop is full", "Worker");
+
+    assert_eq!(
+        dead_letter.to_string(),
+        "Job 3 was not handled by Worker: the queue of the loop is full"
+    );
+}
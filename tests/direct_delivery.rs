@@ -0,0 +1,85 @@
+use pel::PelTestCondvar;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pel::create_event_loops!(
+    events: IncreaseCounter {value: u32}
+
+    active loops:
+        Publisher
+            {cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new())}
+            publishes (IncreaseCounter)
+
+    reactive loops:
+        Subscriber
+            {
+                cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new()),
+                counter: Arc<Mutex<u32>> = Arc::new(Mutex::new(0))
+            }
+            subscribes to (IncreaseCounter)
+            capacity 1 when full Error
+
+    delivery: Direct
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {
+        self.cvar.wait();
+        self.publish_increase_counter(IncreaseCounter::new(3));
+    }
+}
+
+impl SubscriberEventHandlers for Subscriber {
+    fn on_increase_counter(&mut self, event: IncreaseCounter) {
+        *self.counter.lock().unwrap() += event.value;
+        self.cvar.notify();
+    }
+}
+
+#[test]
+fn test_direct_delivery_without_main_loop() {
+    let (_main_event_loop, all_event_loops) = pel_create_event_loops();
+    let publisher_cvar = all_event_loops.publisher.cvar.clone();
+    let subscriber_cvar = all_event_loops.subscriber.cvar.clone();
+    let subscriber_counter = all_event_loops.subscriber.counter.clone();
+
+    // The main loop is not running: events can only reach the subscriber directly
+    pel_launch_event_loops_in_threads(all_event_loops);
+
+    publisher_cvar.notify();
+    subscriber_cvar.wait();
+    assert_eq!(*subscriber_counter.lock().unwrap(), 3);
+
+    publisher_cvar.notify();
+    subscriber_cvar.wait();
+    assert_eq!(*subscriber_counter.lock().unwrap(), 6);
+}
+
+#[test]
+fn test_direct_delivery_to_full_queue() {
+    let (_main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    // The subscriber is not running: its queue is full after the first event
+    let publisher = &all_event_loops.publisher;
+    assert!(publisher
+        .try_publish_increase_counter(IncreaseCounter::new(1))
+        .is_ok());
+    assert!(matches!(
+        publisher.try_publish_increase_counter(IncreaseCounter::new(1)),
+        Err(pel::PelSendError::Full(_))
+    ));
+}
+
+#[test]
+fn test_loops_are_disconnected_once_the_main_event_loop_is_dropped() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    drop(main_event_loop);
+
+    // The loops still hold senders to each other's queues, yet nothing can publish anymore
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        all_event_loops.subscriber.process_events();
+        sender.send(all_event_loops.subscriber.is_running()).unwrap();
+    });
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(false));
+}
@@ -0,0 +1,69 @@
+pel::create_event_loops!(
+    events: Tick {}
+
+    active loops:
+        Publisher {} publishes (Tick),
+
+        DrainAll {count: usize = 0} subscribes to (Tick),
+
+        DrainUpToTwo {count: usize = 0} subscribes to (Tick) drains 2,
+
+        DrainOne {count: usize = 0} subscribes to (Tick) drains one
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for DrainAll {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for DrainUpToTwo {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for DrainOne {
+    fn main_loop(&mut self) {}
+}
+
+impl DrainAllEventHandlers for DrainAll {
+    fn on_tick(&mut self, _event: Tick) {
+        self.count += 1;
+    }
+}
+
+impl DrainUpToTwoEventHandlers for DrainUpToTwo {
+    fn on_tick(&mut self, _event: Tick) {
+        self.count += 1;
+    }
+}
+
+impl DrainOneEventHandlers for DrainOne {
+    fn on_tick(&mut self, _event: Tick) {
+        self.count += 1;
+    }
+}
+
+#[test]
+fn test_active_loops_drain_settings() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    // Everything runs in the test thread: events wait in the queues until processed
+    for _ in 0..5 {
+        all_event_loops.publisher.publish_tick(Tick::new());
+        main_event_loop.dispatch_events();
+    }
+
+    all_event_loops.drain_all.process_events();
+    all_event_loops.drain_up_to_two.process_events();
+    all_event_loops.drain_one.process_events();
+    assert_eq!(all_event_loops.drain_all.count, 5);
+    assert_eq!(all_event_loops.drain_up_to_two.count, 2);
+    assert_eq!(all_event_loops.drain_one.count, 1);
+
+    all_event_loops.drain_up_to_two.process_events();
+    all_event_loops.drain_one.process_events();
+    assert_eq!(all_event_loops.drain_up_to_two.count, 4);
+    assert_eq!(all_event_loops.drain_one.count, 2);
+}
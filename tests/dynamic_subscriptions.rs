@@ -0,0 +1,48 @@
+pel::create_event_loops!(
+    events: Sample {value: u32}
+
+    active loops:
+        Sensor {} publishes (Sample),
+
+        Recorder {samples: Vec<u32> = Vec::new()} subscribes to (Sample) instances 2
+);
+
+impl MainLoop for Sensor {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for Recorder {
+    fn main_loop(&mut self) {}
+}
+
+impl RecorderEventHandlers for Recorder {
+    fn on_sample(&mut self, event: Sample) {
+        self.samples.push(event.value);
+    }
+}
+
+#[test]
+fn test_unsubscribed_events_are_not_routed() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    // Unsubscribing one instance unsubscribes the whole loop
+    all_event_loops.recorder[0].unsubscribe_sample();
+    for value in 0..4 {
+        all_event_loops.sensor.publish_sample(Sample::new(value));
+        main_event_loop.dispatch_events();
+    }
+
+    all_event_loops.recorder[1].subscribe_sample();
+    for value in 4..8 {
+        all_event_loops.sensor.publish_sample(Sample::new(value));
+        main_event_loop.dispatch_events();
+    }
+
+    let mut samples = Vec::new();
+    for recorder in all_event_loops.recorder.iter_mut() {
+        recorder.process_events();
+        samples.extend(recorder.samples.iter().copied());
+    }
+    samples.sort_unstable();
+    assert_eq!(samples, vec![4, 5, 6, 7]);
+}
@@ -0,0 +1,54 @@
+use pel::PelExit;
+use std::time::Duration;
+
+pel::create_event_loops!(
+    events: Job {id: u32}
+
+    active loops:
+        Client {} publishes (Job)
+
+    reactive loops:
+        Worker {} subscribes to (Job)
+);
+
+impl MainLoop for Client {
+    fn main_loop(&mut self) {
+        self.publish_job(Job::new(1));
+        self.publish_job(Job::new(2));
+        self.exit_with(3, "no more jobs").unwrap();
+        // Only the first exit counts
+        self.exit().unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+impl WorkerEventHandlers for Worker {
+    fn on_job(&mut self, _event: Job) {}
+
+    fn on_shutdown(&mut self) {
+        // Sent once the exit of the client was handled: it must not replace it
+        let _ = self.exit_with(4, "the worker shut down");
+    }
+}
+
+#[test]
+fn test_first_exit_code_and_reason_are_returned() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let threads = pel_launch_event_loops_in_threads(all_event_loops);
+    let exit = pel_run_main_loop_indefinitely(main_event_loop);
+    threads.join(Duration::from_secs(5));
+
+    assert_eq!(exit, PelExit::new(3, "no more jobs"));
+    assert!(!exit.is_success());
+}
+
+#[test]
+fn test_codes_out_of_range_are_reported_as_failures() {
+    use std::process::{ExitCode, Termination};
+
+    assert_eq!(PelExit::new(0, "done").report(), ExitCode::SUCCESS);
+    assert_eq!(PelExit::new(3, "failed").report(), ExitCode::from(3));
+    assert_eq!(PelExit::new(256, "failed").report(), ExitCode::FAILURE);
+    assert_eq!(PelExit::new(-1, "failed").report(), ExitCode::FAILURE);
+}
@@ -0,0 +1,57 @@
+pel::create_event_loops!(
+    events: Order {venue: String, quantity: u32}
+
+    active loops:
+        Publisher {} publishes (Order),
+
+        NasdaqRecorder {quantities: Vec<u32> = Vec::new()}
+            subscribes to (Order by ref where |order| order.venue == "XNAS"),
+
+        BigOrderRecorder {quantities: Vec<u32> = Vec::new()}
+            subscribes to (Order where |order| order.quantity >= 100)
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for NasdaqRecorder {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for BigOrderRecorder {
+    fn main_loop(&mut self) {}
+}
+
+impl NasdaqRecorderEventHandlers for NasdaqRecorder {
+    fn on_order(&mut self, event: &Order) {
+        self.quantities.push(event.quantity);
+    }
+}
+
+impl BigOrderRecorderEventHandlers for BigOrderRecorder {
+    fn on_order(&mut self, event: Order) {
+        self.quantities.push(event.quantity);
+    }
+}
+
+#[test]
+fn test_filtered_events_are_not_delivered() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    let publisher = &all_event_loops.publisher;
+    publisher.publish_order(Order::new("XNAS".to_string(), 1));
+    publisher.publish_order(Order::new("XPAR".to_string(), 200));
+    publisher.publish_order(Order::new("XNAS".to_string(), 300));
+    for _ in 0..3 {
+        main_event_loop.dispatch_events();
+    }
+
+    all_event_loops.nasdaq_recorder.process_events();
+    all_event_loops.big_order_recorder.process_events();
+    assert_eq!(all_event_loops.nasdaq_recorder.quantities, vec![1, 300]);
+    assert_eq!(
+        all_event_loops.big_order_recorder.quantities,
+        vec![200, 300]
+    );
+}
@@ -0,0 +1,57 @@
+use pel::PelSendError;
+use std::time::Duration;
+
+pel::create_event_loops!(
+    events: Refused {id: u32}, Blocked {}
+
+    reactive loops:
+        Strict {ids: Vec<u32> = Vec::new()} subscribes to (Refused) capacity 1 when full Error,
+        Patient {} subscribes to (Blocked) capacity 1 when full Block
+);
+
+impl StrictEventHandlers for Strict {
+    fn on_refused(&mut self, event: Refused) {
+        self.ids.push(event.id);
+    }
+}
+
+impl PatientEventHandlers for Patient {
+    fn on_blocked(&mut self, _event: Blocked) {}
+}
+
+#[test]
+fn test_full_queue_returns_an_error_to_the_publisher() {
+    let (main_event_loop, mut all_event_loops, publisher) = pel_create_event_loops_with_publisher();
+
+    publisher.try_publish_refused(Refused::new(1)).unwrap();
+    main_event_loop.dispatch_events();
+    // The queue of the subscriber is full: the error reaches the publisher
+    assert!(matches!(
+        publisher.try_publish_refused(Refused::new(2)),
+        Err(PelSendError::Full(_))
+    ));
+
+    // The refused event was only sent as a dead letter: once the queue has room, only the next
+    // one is handled
+    all_event_loops.strict.process_events();
+    assert!(publisher.try_publish_refused(Refused::new(3)).is_ok());
+    main_event_loop.dispatch_events();
+    main_event_loop.dispatch_events();
+    all_event_loops.strict.process_events();
+    assert_eq!(all_event_loops.strict.ids, vec![1, 3]);
+}
+
+#[test]
+fn test_full_queue_blocks_the_publisher() {
+    let (main_event_loop, mut all_event_loops, publisher) = pel_create_event_loops_with_publisher();
+
+    publisher.publish_blocked(Blocked::new());
+    main_event_loop.dispatch_events();
+    let blocked_publisher =
+        std::thread::spawn(move || publisher.try_publish_blocked(Blocked::new()));
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!blocked_publisher.is_finished());
+    all_event_loops.patient.process_events();
+    assert!(blocked_publisher.join().unwrap().is_ok());
+}
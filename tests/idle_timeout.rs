@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+pel::create_event_loops!(
+    events: Keystroke {key: char}
+
+    active loops:
+        Keyboard {} publishes (Keystroke)
+
+    reactive loops:
+        Session {keys: String = String::new(), idle_periods: Vec<Duration> = Vec::new()}
+            subscribes to (Keystroke) idle after 30ms
+);
+
+impl MainLoop for Keyboard {
+    fn main_loop(&mut self) {}
+}
+
+impl SessionEventHandlers for Session {
+    fn on_keystroke(&mut self, event: Keystroke) {
+        self.keys.push(event.key);
+    }
+
+    fn on_idle(&mut self, idle_for: Duration) {
+        self.idle_periods.push(idle_for);
+    }
+}
+
+#[test]
+fn test_idle_handler_is_called_until_an_event_arrives() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    let start = Instant::now();
+
+    while all_event_loops.session.idle_periods.len() < 2 {
+        all_event_loops.session.process_events();
+    }
+    let idle_periods = all_event_loops.session.idle_periods.clone();
+    assert!(idle_periods[0] >= Duration::from_millis(30));
+    assert!(idle_periods[1] >= Duration::from_millis(60));
+    assert!(start.elapsed() >= Duration::from_millis(60));
+
+    // An event resets the idle timeout
+    all_event_loops
+        .keyboard
+        .publish_keystroke(Keystroke::new('a'));
+    main_event_loop.dispatch_events();
+    all_event_loops.session.process_events();
+    let event_received = Instant::now();
+    all_event_loops.session.process_events();
+
+    assert_eq!(all_event_loops.session.keys, "a");
+    assert_eq!(all_event_loops.session.idle_periods.len(), 3);
+    assert!(all_event_loops.session.idle_periods[2] >= Duration::from_millis(30));
+    assert!(event_received.elapsed() >= Duration::from_millis(30));
+}
@@ -0,0 +1,90 @@
+pel::create_event_loops!(
+    events: Trade {#[key] account: u64, sequence: u32}
+
+    active loops:
+        Publisher {} publishes (Trade),
+
+        TradeBook {trades: Vec<(u64, u32)> = Vec::new()}
+            subscribes to (Trade) instances 3 balanced by HashByKey,
+
+        DefaultTradeBook {trades: Vec<(u64, u32)> = Vec::new()}
+            subscribes to (Trade) instances 3
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for TradeBook {
+    fn main_loop(&mut self) {}
+}
+
+impl TradeBookEventHandlers for TradeBook {
+    fn on_trade(&mut self, event: Trade) {
+        self.trades.push((event.account, event.sequence));
+    }
+}
+
+impl MainLoop for DefaultTradeBook {
+    fn main_loop(&mut self) {}
+}
+
+impl DefaultTradeBookEventHandlers for DefaultTradeBook {
+    fn on_trade(&mut self, event: Trade) {
+        self.trades.push((event.account, event.sequence));
+    }
+}
+
+fn assert_accounts_are_handled_in_order_by_one_instance(instances: &[&Vec<(u64, u32)>]) {
+    for account in 0..10 {
+        let instances_with_account = instances
+            .iter()
+            .filter(|trades| trades.iter().any(|trade| trade.0 == account))
+            .collect::<Vec<_>>();
+        assert_eq!(instances_with_account.len(), 1);
+
+        // Trades of the same account are handled in order
+        let sequences = instances_with_account[0]
+            .iter()
+            .filter(|trade| trade.0 == account)
+            .map(|trade| trade.1)
+            .collect::<Vec<_>>();
+        assert_eq!(sequences, vec![0, 1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn test_events_with_the_same_key_reach_the_same_instance() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    for sequence in 0..5 {
+        for account in 0..10 {
+            all_event_loops
+                .publisher
+                .publish_trade(Trade::new(account, sequence));
+            main_event_loop.dispatch_events();
+        }
+    }
+
+    for trade_book in all_event_loops.trade_book.iter_mut() {
+        trade_book.process_events();
+    }
+    for trade_book in all_event_loops.default_trade_book.iter_mut() {
+        trade_book.process_events();
+    }
+    assert_accounts_are_handled_in_order_by_one_instance(
+        &all_event_loops
+            .trade_book
+            .iter()
+            .map(|trade_book| &trade_book.trades)
+            .collect::<Vec<_>>(),
+    );
+    // HashByKey is the default
+    assert_accounts_are_handled_in_order_by_one_instance(
+        &all_event_loops
+            .default_trade_book
+            .iter()
+            .map(|trade_book| &trade_book.trades)
+            .collect::<Vec<_>>(),
+    );
+}
@@ -0,0 +1,48 @@
+use pel::PelExit;
+
+// Both systems log to a file, only the first one initializes the logger
+pel::create_event_loops!(
+    system: Gateway
+
+    events: Request {}
+
+    reactive loops:
+        Stopper {}
+
+    log file: "target/gateway.log"
+);
+
+pel::create_event_loops!(
+    system: Worker
+
+    events: Job {}
+
+    reactive loops:
+        Stopper {}
+
+    log file: "target/worker.log"
+);
+
+impl gateway::StopperEventHandlers for gateway::Stopper {
+    fn on_start(&mut self) {
+        let _ = self.exit_with(1, "the gateway stopped");
+    }
+}
+
+impl worker::StopperEventHandlers for worker::Stopper {
+    fn on_start(&mut self) {
+        let _ = self.exit_with(2, "the worker stopped");
+    }
+}
+
+#[test]
+fn test_systems_logging_to_a_file_run_one_after_the_other() {
+    let _ = std::fs::remove_file("target/gateway.log");
+
+    assert_eq!(gateway::pel_main(), PelExit::new(1, "the gateway stopped"));
+    assert_eq!(worker::pel_main(), PelExit::new(2, "the worker stopped"));
+
+    // The second system warns through the logger of the first one
+    let log = std::fs::read_to_string("target/gateway.log").unwrap();
+    assert!(log.contains("The log configuration of logging::worker was not applied"));
+}
@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+pel::create_event_loops!(
+    events: Sample {value: u32}
+
+    active loops:
+        Sensor {} publishes (Sample)
+
+    reactive loops:
+        Heartbeat {ticks: Vec<Instant> = Vec::new()} every 20ms => on_tick,
+
+        Averager {samples: Vec<u32> = Vec::new(), n_ticks: usize = 0}
+            subscribes to (Sample) every 1h => on_flush
+);
+
+impl MainLoop for Sensor {
+    fn main_loop(&mut self) {}
+}
+
+impl HeartbeatEventHandlers for Heartbeat {
+    fn on_tick(&mut self) {
+        self.ticks.push(Instant::now());
+        // Handling a tick late does not delay the next ones
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+impl AveragerEventHandlers for Averager {
+    fn on_sample(&mut self, event: Sample) {
+        self.samples.push(event.value);
+    }
+
+    fn on_flush(&mut self) {
+        self.n_ticks += 1;
+    }
+}
+
+#[test]
+fn test_ticks_are_periodic() {
+    let start = Instant::now();
+    let (_main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    while all_event_loops.heartbeat.ticks.len() < 5 {
+        all_event_loops.heartbeat.process_events();
+    }
+
+    // Only lower bounds: a loaded machine can delay any tick
+    let ticks = &all_event_loops.heartbeat.ticks;
+    for (n_tick, tick) in ticks.iter().enumerate() {
+        assert!(*tick >= start + Duration::from_millis(20) * (n_tick as u32 + 1));
+    }
+    assert!(ticks.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_events_are_handled_between_ticks() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    all_event_loops.sensor.publish_sample(Sample::new(4));
+    main_event_loop.dispatch_events();
+    all_event_loops.averager.process_events();
+
+    assert_eq!(all_event_loops.averager.samples, vec![4]);
+    assert_eq!(all_event_loops.averager.n_ticks, 0);
+}
+
+#[test]
+fn test_periods_are_parsed_with_their_unit() {
+    assert_eq!(pel::pel_parse_duration("250us"), Duration::from_micros(250));
+    assert_eq!(pel::pel_parse_duration("1.5s"), Duration::from_millis(1500));
+    assert_eq!(pel::pel_parse_duration("2m"), Duration::from_secs(120));
+}
+
+#[test]
+#[should_panic(expected = "the unit must be")]
+fn test_periods_with_an_unknown_unit_are_refused() {
+    pel::pel_parse_duration("5xs");
+}
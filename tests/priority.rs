@@ -0,0 +1,45 @@
+pel::create_event_loops!(
+    events: Data {value: u32}, Control {value: u32} priority 1
+
+    active loops:
+        Publisher {} publishes (Data, Control),
+
+        Recorder {received: Vec<u32> = Vec::new()} subscribes to (Data, Control)
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for Recorder {
+    fn main_loop(&mut self) {}
+}
+
+impl RecorderEventHandlers for Recorder {
+    fn on_data(&mut self, event: Data) {
+        self.received.push(event.value);
+    }
+
+    fn on_control(&mut self, event: Control) {
+        self.received.push(event.value);
+    }
+}
+
+#[test]
+fn test_control_events_overtake_data_events() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    let publisher = &all_event_loops.publisher;
+    publisher.publish_data(Data::new(1));
+    publisher.publish_data(Data::new(2));
+    publisher.publish_control(Control::new(10));
+    publisher.publish_data(Data::new(3));
+    publisher.publish_control(Control::new(20));
+
+    // The control events are dispatched first, in the order they were published
+    for _ in 0..5 {
+        main_event_loop.dispatch_events();
+    }
+    all_event_loops.recorder.process_events();
+    assert_eq!(all_event_loops.recorder.received, vec![10, 20, 1, 2, 3]);
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+// Stands for a library crate exporting its systems
+mod library {
+    pub use self::shop::*;
+    use std::time::Duration;
+
+    pel::create_event_loops!(
+        pub system: Shop
+
+        events: Order {item: String} -> u32
+
+        active loops:
+            Cashier {} publishes (Order)
+
+        reactive loops:
+            Stock {n_orders: u32 = 0} subscribes to (Order)
+    );
+
+    impl shop::MainLoop for shop::Cashier {
+        fn main_loop(&mut self) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    impl shop::StockEventHandlers for shop::Stock {
+        fn on_order(&mut self, _event: shop::Order) -> u32 {
+            self.n_orders += 1;
+            self.n_orders
+        }
+    }
+}
+
+mod top_level {
+    pel::create_event_loops!(
+        pub events: Ping {}
+
+        active loops:
+            Pinger {} publishes (Ping)
+    );
+
+    impl MainLoop for Pinger {
+        fn main_loop(&mut self) {
+            let _ = self.exit_with(4, "pinged");
+        }
+    }
+}
+
+#[test]
+fn test_public_system_is_launched_from_another_module() {
+    let (main_event_loop, all_event_loops) = library::shop::pel_create_event_loops();
+    let library::PelAllEventLoops { cashier, stock } = all_event_loops;
+
+    let first = cashier.request_order(library::Order::new("apple".to_string()));
+    let second = cashier.request_order(library::Order::new("pear".to_string()));
+    let mut threads = pel::PelThreads::new();
+    threads.spawn("Stock", move || {
+        let mut stock = stock;
+        while stock.is_running() {
+            stock.process_events();
+        }
+    });
+    main_event_loop.dispatch_events();
+    main_event_loop.dispatch_events();
+
+    assert_eq!(first.wait_timeout(Duration::from_secs(5)), Ok(1));
+    assert_eq!(second.wait_timeout(Duration::from_secs(5)), Ok(2));
+    let _ = cashier.exit();
+    main_event_loop.dispatch_events();
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+}
+
+#[test]
+fn test_public_entry_points_are_launched_from_another_module() {
+    let (main_event_loop, all_event_loops) = top_level::pel_create_event_loops();
+
+    let threads = top_level::pel_launch_event_loops_in_threads(all_event_loops);
+    let exit = top_level::pel_run_main_loop_indefinitely(main_event_loop);
+
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+    assert_eq!(exit.code, 4);
+}
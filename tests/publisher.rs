@@ -0,0 +1,57 @@
+use std::sync::{Arc, Mutex};
+
+pel::create_event_loops!(
+    events: Add {amount: u32}, Reset {}
+
+    reactive loops:
+        Adder {total: Arc<Mutex<u32>> = Arc::new(Mutex::new(0))} subscribes to (Add, Reset)
+);
+
+impl AdderEventHandlers for Adder {
+    fn on_add(&mut self, event: Add) {
+        *self.total.lock().unwrap() += event.amount;
+    }
+
+    fn on_reset(&mut self, _event: Reset) {
+        *self.total.lock().unwrap() = 0;
+    }
+}
+
+#[test]
+fn test_events_are_published_from_other_threads() {
+    let (main_event_loop, mut all_event_loops, publisher) = pel_create_event_loops_with_publisher();
+    let total = all_event_loops.adder.total.clone();
+
+    let threads = (1..=3)
+        .map(|amount| {
+            let publisher = publisher.clone();
+            std::thread::spawn(move || publisher.publish_add(Add::new(amount)))
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    for _ in 0..3 {
+        main_event_loop.dispatch_events();
+        all_event_loops.adder.process_events();
+    }
+    assert_eq!(*total.lock().unwrap(), 6);
+
+    publisher.try_publish_reset(Reset::new()).unwrap();
+    main_event_loop.dispatch_events();
+    all_event_loops.adder.process_events();
+    assert_eq!(*total.lock().unwrap(), 0);
+
+    // Nothing dispatches the events once the main event loop is dropped
+    drop(main_event_loop);
+    assert!(publisher.try_publish_add(Add::new(1)).is_err());
+}
+
+#[test]
+fn test_external_events_are_logged_as_such() {
+    let event = PelAllEvents::PelInternalExternalEvent(Box::new(Add::new(4).into()));
+    assert_eq!(
+        event.to_string(),
+        "Add : amount = 4,  (published by external)"
+    );
+}
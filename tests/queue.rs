@@ -0,0 +1,98 @@
+use pel::{pel_channel, PelBackpressure, PelBalance, PelPoolSender, PelSendError};
+use std::sync::mpsc::{RecvError, TryRecvError};
+
+#[test]
+fn test_backpressure_policies() {
+    let (sender, receiver) = pel_channel("test", Some(2), PelBackpressure::DropOldest, |_| 0);
+    for i in 0..4 {
+        assert!(sender.send(i).is_ok());
+    }
+    assert_eq!(receiver.try_recv(), Ok(2));
+    assert_eq!(receiver.try_recv(), Ok(3));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    let (sender, receiver) = pel_channel("test", Some(2), PelBackpressure::DropNewest, |_| 0);
+    for i in 0..4 {
+        assert!(sender.send(i).is_ok());
+    }
+    assert_eq!(receiver.try_recv(), Ok(0));
+    assert_eq!(receiver.try_recv(), Ok(1));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    let (sender, receiver) = pel_channel("test", Some(1), PelBackpressure::Error, |_| 0);
+    assert!(sender.send(0).is_ok());
+    assert!(matches!(sender.send(1), Err(PelSendError::Full(1))));
+    assert_eq!(receiver.recv(), Ok(0));
+
+    drop(receiver);
+    assert!(matches!(sender.send(2), Err(PelSendError::Disconnected(2))));
+}
+
+#[test]
+fn test_blocked_sender_waits_for_room() {
+    let (sender, receiver) = pel_channel("test", Some(1), PelBackpressure::Block, |_| 0);
+    sender.send(0).unwrap();
+    let publisher = std::thread::spawn(move || sender.send(1).is_ok());
+
+    assert_eq!(receiver.recv(), Ok(0));
+    assert_eq!(receiver.recv(), Ok(1));
+    assert!(publisher.join().unwrap());
+    // Every sender was dropped
+    assert_eq!(receiver.recv(), Err(RecvError));
+}
+
+#[test]
+fn test_higher_priorities_are_received_first() {
+    // The tens give the priority
+    let (sender, receiver) = pel_channel("test", Some(5), PelBackpressure::DropOldest, |i: &u8| {
+        i / 10
+    });
+    for i in &[1, 21, 2, 11, 22, 3] {
+        sender.send(*i).unwrap();
+    }
+    // 1 was the oldest event with the lowest priority
+    for i in &[21, 22, 11, 2, 3] {
+        assert_eq!(receiver.try_recv(), Ok(*i));
+    }
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+#[should_panic(expected = "The test queue must have a capacity of at least 1")]
+fn test_capacity_of_zero_is_refused() {
+    let _ = pel_channel::<u8>("test", Some(0), PelBackpressure::Block, |_| 0);
+}
+
+#[test]
+fn test_accepted_items_are_queued_past_the_capacity() {
+    // Another sender took the last place between the wait for room and the send
+    let (sender, receiver) = pel_channel("test", Some(1), PelBackpressure::Block, |_| 0);
+    assert!(sender.wait_for_room());
+    sender.send(0).unwrap();
+    sender.send_accepted(1).unwrap();
+    assert_eq!(receiver.len(), 2);
+
+    let (sender, receiver) = pel_channel("test", Some(1), PelBackpressure::Error, |_| 0);
+    assert!(sender.wait_for_room());
+    sender.send(0).unwrap();
+    sender.send_accepted(1).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(0));
+    assert_eq!(receiver.try_recv(), Ok(1));
+}
+
+#[test]
+fn test_accepted_items_go_to_an_instance_with_room() {
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..2)
+        .map(|_| pel_channel("test", Some(1), PelBackpressure::Block, |_| 0))
+        .unzip();
+    let pool = PelPoolSender::new(senders, PelBalance::RoundRobin);
+    pool.send(0, None).unwrap();
+    pool.send(1, None).unwrap();
+    assert_eq!(receivers[1].try_recv(), Ok(1));
+
+    // Round-robin would pick the first instance, which is full
+    assert!(pool.wait_for_room(None));
+    pool.send_accepted(2, None).unwrap();
+    assert_eq!(receivers[0].len(), 1);
+    assert_eq!(receivers[1].try_recv(), Ok(2));
+}
@@ -0,0 +1,90 @@
+use pel::PelResponseError;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pel::create_event_loops!(
+    events: GetBalance {account: u64} -> u64,
+            GetAccounts {} -> (Vec<u64>),
+            Deposit {account: u64, amount: u64}
+
+    active loops:
+        Teller {} publishes (GetBalance, GetAccounts, Deposit),
+
+        Bank {balances: HashMap<u64, u64> = HashMap::new()}
+            subscribes to (GetBalance by ref, GetAccounts, Deposit)
+);
+
+impl MainLoop for Teller {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for Bank {
+    fn main_loop(&mut self) {}
+}
+
+impl BankEventHandlers for Bank {
+    fn on_get_balance(&mut self, event: &GetBalance) -> u64 {
+        self.balances.get(&event.account).copied().unwrap_or(0)
+    }
+
+    fn on_get_accounts(&mut self, _event: GetAccounts) -> Vec<u64> {
+        let mut accounts = self.balances.keys().copied().collect::<Vec<_>>();
+        accounts.sort_unstable();
+        accounts
+    }
+
+    fn on_deposit(&mut self, event: Deposit) {
+        *self.balances.entry(event.account).or_insert(0) += event.amount;
+    }
+}
+
+#[test]
+fn test_requests_are_answered_by_the_handler() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+
+    all_event_loops.teller.publish_deposit(Deposit::new(7, 100));
+    all_event_loops.teller.publish_deposit(Deposit::new(3, 20));
+    let balance = all_event_loops
+        .teller
+        .request_get_balance(GetBalance::new(7));
+    let accounts = all_event_loops
+        .teller
+        .request_get_accounts(GetAccounts::new());
+    for _ in 0..4 {
+        main_event_loop.dispatch_events();
+    }
+
+    // A request published after the deposits is answered with the deposited amount
+    let later_balance = all_event_loops
+        .teller
+        .request_get_balance(GetBalance::new(3));
+    main_event_loop.dispatch_events();
+    all_event_loops.bank.process_events();
+
+    assert_eq!(balance.wait_timeout(Duration::from_secs(1)), Ok(100));
+    assert_eq!(accounts.wait(), Ok(vec![3, 7]));
+    assert_eq!(later_balance.wait_timeout(Duration::from_secs(1)), Ok(20));
+}
+
+#[test]
+fn test_waiting_for_a_response_times_out() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let balance = all_event_loops
+        .teller
+        .request_get_balance(GetBalance::new(7));
+    main_event_loop.dispatch_events();
+
+    assert_eq!(
+        balance.wait_timeout(Duration::from_millis(10)),
+        Err(PelResponseError::Timeout)
+    );
+
+    // The request is dropped with the bank
+    let balance = all_event_loops
+        .teller
+        .request_get_balance(GetBalance::new(7));
+    main_event_loop.dispatch_events();
+    drop(all_event_loops.bank);
+    assert_eq!(balance.wait(), Err(PelResponseError::NoResponder));
+}
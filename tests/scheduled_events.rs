@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+pel::create_event_loops!(
+    events: Reminder {id: u32}
+
+    active loops:
+        Scheduler {} publishes (Reminder),
+
+        Agenda {reminders: Vec<u32> = Vec::new()} subscribes to (Reminder)
+
+    delivery: Direct
+);
+
+impl MainLoop for Scheduler {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for Agenda {
+    fn main_loop(&mut self) {}
+}
+
+impl AgendaEventHandlers for Agenda {
+    fn on_reminder(&mut self, event: Reminder) {
+        self.reminders.push(event.id);
+    }
+}
+
+#[test]
+fn test_scheduled_events_are_published_in_time_order() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    let start = Instant::now();
+
+    let scheduler = &all_event_loops.scheduler;
+    scheduler.publish_reminder_after(Duration::from_millis(60), Reminder::new(3));
+    scheduler.publish_reminder_at(start + Duration::from_millis(20), Reminder::new(1));
+    let cancelled = scheduler.publish_reminder_after(Duration::from_millis(40), Reminder::new(2));
+    for _ in 0..3 {
+        main_event_loop.dispatch_events();
+    }
+    cancelled.cancel();
+
+    // Nothing is published before the first deadline
+    all_event_loops.agenda.process_events();
+    assert!(all_event_loops.agenda.reminders.is_empty());
+
+    while all_event_loops.agenda.reminders.len() < 2 {
+        main_event_loop.dispatch_events();
+        all_event_loops.agenda.process_events();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(all_event_loops.agenda.reminders, vec![1, 3]);
+}
+
+pel::create_event_loops!(
+    system: Busy
+    events: Ping {}
+
+    reactive loops:
+        Pinger {} publishes (Ping),
+
+        Inbox {} subscribes to (Ping) capacity 1 when full Error,
+
+        Auditor {receivers: Vec<&'static str> = Vec::new()} on dead letter => on_dead_letter
+);
+
+impl busy::PingerEventHandlers for busy::Pinger {}
+
+impl busy::InboxEventHandlers for busy::Inbox {
+    fn on_ping(&mut self, _event: busy::Ping) {}
+}
+
+impl busy::AuditorEventHandlers for busy::Auditor {
+    fn on_dead_letter(&mut self, dead_letter: pel::PelDeadLetter<busy::PelAllEvents>) {
+        self.receivers.push(dead_letter.receiver);
+    }
+}
+
+#[test]
+fn test_scheduled_events_refused_by_a_full_queue_are_dead_letters() {
+    let (main_event_loop, mut all_event_loops) = busy::pel_create_event_loops();
+    all_event_loops.pinger.publish_ping(busy::Ping::new());
+    main_event_loop.dispatch_events();
+
+    // Scheduled, then published by the main event loop, which gets the dead letter back
+    all_event_loops
+        .pinger
+        .publish_ping_at(Instant::now(), busy::Ping::new());
+    for _ in 0..3 {
+        main_event_loop.dispatch_events();
+    }
+    all_event_loops.auditor.process_events();
+    assert_eq!(all_event_loops.auditor.receivers, vec!["Inbox"]);
+}
@@ -0,0 +1,88 @@
+use pel::PelTestCondvar;
+use std::sync::{Arc, Mutex};
+
+pel::create_event_loops!(
+    events: BigEvent {values: Vec<usize>}
+
+    active loops:
+        Publisher
+            {cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new())}
+            publishes (BigEvent)
+
+    reactive loops:
+        SharedSubscriber1
+            {
+                cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new()),
+                received: Arc<Mutex<Option<Arc<BigEvent>>>> = Arc::new(Mutex::new(None))
+            }
+            subscribes to (BigEvent by arc),
+
+        SharedSubscriber2
+            {
+                cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new()),
+                received: Arc<Mutex<Option<Arc<BigEvent>>>> = Arc::new(Mutex::new(None))
+            }
+            subscribes to (BigEvent by arc),
+
+        BorrowingSubscriber
+            {
+                cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new()),
+                sum: Arc<Mutex<usize>> = Arc::new(Mutex::new(0))
+            }
+            subscribes to (BigEvent by ref)
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {
+        self.cvar.wait();
+        self.publish_big_event(BigEvent::new(vec![1; 100]));
+    }
+}
+
+impl SharedSubscriber1EventHandlers for SharedSubscriber1 {
+    fn on_big_event(&mut self, event: Arc<BigEvent>) {
+        *self.received.lock().unwrap() = Some(event);
+        self.cvar.notify();
+    }
+}
+
+impl SharedSubscriber2EventHandlers for SharedSubscriber2 {
+    fn on_big_event(&mut self, event: Arc<BigEvent>) {
+        *self.received.lock().unwrap() = Some(event);
+        self.cvar.notify();
+    }
+}
+
+impl BorrowingSubscriberEventHandlers for BorrowingSubscriber {
+    fn on_big_event(&mut self, event: &BigEvent) {
+        *self.sum.lock().unwrap() += event.values.iter().sum::<usize>();
+        self.cvar.notify();
+    }
+}
+
+#[test]
+fn test_events_are_shared_between_subscribers() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+    let publisher_cvar = all_event_loops.publisher.cvar.clone();
+    let subscriber1_cvar = all_event_loops.shared_subscriber1.cvar.clone();
+    let subscriber2_cvar = all_event_loops.shared_subscriber2.cvar.clone();
+    let borrowing_cvar = all_event_loops.borrowing_subscriber.cvar.clone();
+    let received1 = all_event_loops.shared_subscriber1.received.clone();
+    let received2 = all_event_loops.shared_subscriber2.received.clone();
+    let sum = all_event_loops.borrowing_subscriber.sum.clone();
+
+    pel_launch_event_loops_in_threads(all_event_loops);
+    std::thread::spawn(move || pel_run_main_loop_indefinitely(main_event_loop));
+
+    publisher_cvar.notify();
+    subscriber1_cvar.wait();
+    subscriber2_cvar.wait();
+    borrowing_cvar.wait();
+
+    // Both subscribers received the very same event: it was not copied
+    let event1 = received1.lock().unwrap().clone().unwrap();
+    let event2 = received2.lock().unwrap().clone().unwrap();
+    assert!(Arc::ptr_eq(&event1, &event2));
+    assert_eq!(event1.values.len(), 100);
+    assert_eq!(*sum.lock().unwrap(), 100);
+}
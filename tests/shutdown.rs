@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pel::create_event_loops!(
+    events: Line {text: String}
+
+    active loops:
+        Producer {n_lines: u32 = 0} publishes (Line),
+
+        Counter {n_lines: u32 = 0, log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()))}
+            subscribes to (Line)
+
+    reactive loops:
+        Writer {buffer: Vec<String> = Vec::new(), log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()))}
+            subscribes to (Line) instances 2
+);
+
+impl MainLoop for Producer {
+    fn main_loop(&mut self) {
+        if self.n_lines < 10 {
+            self.publish_line(Line::new(self.n_lines.to_string()));
+            self.n_lines += 1;
+        } else if self.n_lines == 10 {
+            self.exit().unwrap();
+            self.n_lines += 1;
+        }
+    }
+}
+
+impl MainLoop for Counter {
+    fn main_loop(&mut self) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    fn on_shutdown(&mut self) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("counted {}", self.n_lines));
+    }
+}
+
+impl CounterEventHandlers for Counter {
+    fn on_line(&mut self, _event: Line) {
+        self.n_lines += 1;
+    }
+}
+
+impl WriterEventHandlers for Writer {
+    fn on_line(&mut self, event: Line) {
+        self.buffer.push(event.text);
+    }
+
+    fn on_shutdown(&mut self) {
+        // Flush the buffer
+        self.log.lock().unwrap().append(&mut self.buffer);
+    }
+}
+
+pel::create_event_loops!(
+    system: Loaded
+    events: Work {}
+
+    reactive loops:
+        Slow {n_works: u32 = 0} subscribes to (Work) capacity 1 when full DropNewest
+);
+
+impl loaded::SlowEventHandlers for loaded::Slow {
+    fn on_work(&mut self, _event: loaded::Work) {
+        self.n_works += 1;
+    }
+}
+
+#[test]
+fn test_exit_shuts_down_every_loop_after_its_pending_events() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+    let counter_log = all_event_loops.counter.log.clone();
+    let writer_logs = all_event_loops
+        .writer
+        .iter()
+        .map(|writer| writer.log.clone())
+        .collect::<Vec<_>>();
+
+    let threads = pel_launch_event_loops_in_threads(all_event_loops);
+    pel_run_main_loop_indefinitely(main_event_loop);
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+
+    assert_eq!(*counter_log.lock().unwrap(), vec!["counted 10"]);
+    let mut lines = writer_logs
+        .iter()
+        .flat_map(|log| log.lock().unwrap().clone())
+        .map(|line| line.parse::<u32>().unwrap())
+        .collect::<Vec<_>>();
+    lines.sort_unstable();
+    assert_eq!(lines, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_join_waits_for_the_threads_at_most_for_the_grace_period() {
+    let mut threads = pel::PelThreads::new();
+    threads.spawn("Quick", || {});
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    threads.spawn("Blocked", move || {
+        let _ = receiver.recv();
+    });
+
+    let start = Instant::now();
+    assert_eq!(threads.join(Duration::from_millis(100)), 1);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!(start.elapsed() < Duration::from_secs(2));
+    drop(sender);
+}
+
+#[test]
+fn test_full_queues_still_get_the_shutdown() {
+    let (main_event_loop, mut all_event_loops, publisher) =
+        loaded::pel_create_event_loops_with_publisher();
+    for _ in 0..2 {
+        publisher.publish_work(loaded::Work::new());
+        main_event_loop.dispatch_events();
+    }
+    main_event_loop.shut_down(pel::PelExit::new(0, "done"));
+
+    // The second event was dropped, not the shutdown
+    while all_event_loops.slow.try_process_events() {}
+    assert_eq!(all_event_loops.slow.n_works, 1);
+    assert!(!all_event_loops.slow.is_running());
+}
+
+#[test]
+fn test_loops_are_shut_down_once_the_main_event_loop_is_dropped() {
+    // The publisher does not keep the queues of the loops connected
+    let (main_event_loop, mut all_event_loops, _publisher) =
+        loaded::pel_create_event_loops_with_publisher();
+    drop(main_event_loop);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        all_event_loops.slow.process_events();
+        sender.send(all_event_loops.slow.is_running()).unwrap();
+    });
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(false));
+}
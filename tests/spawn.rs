@@ -0,0 +1,50 @@
+use pel::PelExit;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+static TOTAL: AtomicU32 = AtomicU32::new(0);
+
+pel::create_event_loops!(
+    events: Add {amount: u32}
+
+    reactive loops:
+        Adder {} subscribes to (Add)
+);
+
+impl AdderEventHandlers for Adder {
+    fn on_add(&mut self, event: Add) {
+        TOTAL.fetch_add(event.amount, Ordering::SeqCst);
+    }
+}
+
+fn wait_until(condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    true
+}
+
+#[test]
+fn test_spawned_system_is_controlled_by_its_handle() {
+    let system = pel_spawn();
+    assert!(system.is_running());
+    assert_eq!(system.n_running(), 1);
+
+    system.publish(Add::new(2)).unwrap();
+    system.publish(Add::new(3)).unwrap();
+    assert!(wait_until(|| TOTAL.load(Ordering::SeqCst) == 5));
+
+    system.shutdown_with(4, "done adding").unwrap();
+    assert!(wait_until(
+        || !system.is_running() && system.n_running() == 0
+    ));
+    // Nothing dispatches the events anymore
+    assert!(system.publish(Add::new(1)).is_err());
+    assert!(system.shutdown().is_err());
+
+    assert_eq!(system.join(), PelExit::new(4, "done adding"));
+}
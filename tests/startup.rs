@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pel::create_event_loops!(
+    events: Greeting {text: String}
+
+    active loops:
+        Publisher {} publishes (Greeting)
+
+    reactive loops:
+        Database {log: Arc<Mutex<Vec<String>>> = Arc::default()},
+
+        Greeter {log: Arc<Mutex<Vec<String>>> = Arc::default()} starts after (Database),
+
+        Printer {log: Arc<Mutex<Vec<String>>> = Arc::default()} subscribes to (Greeting)
+
+    startup: Barrier
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    fn on_start(&mut self) {
+        // Published before the other loops are started
+        self.publish_greeting(Greeting::new("hello".to_string()));
+    }
+}
+
+impl DatabaseEventHandlers for Database {
+    fn on_start(&mut self) {
+        std::thread::sleep(Duration::from_millis(30));
+        self.log
+            .lock()
+            .unwrap()
+            .push("database started".to_string());
+    }
+}
+
+impl GreeterEventHandlers for Greeter {
+    fn on_start(&mut self) {
+        self.log.lock().unwrap().push("greeter started".to_string());
+    }
+}
+
+impl PrinterEventHandlers for Printer {
+    fn on_greeting(&mut self, event: Greeting) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("printed {}", event.text));
+        self.exit().unwrap();
+    }
+}
+
+#[test]
+fn test_events_are_delivered_once_every_loop_is_started() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    all_event_loops.database.log = log.clone();
+    all_event_loops.greeter.log = log.clone();
+    all_event_loops.printer.log = log.clone();
+
+    let threads = pel_launch_event_loops_in_threads(all_event_loops);
+    pel_run_main_loop_indefinitely(main_event_loop);
+    threads.join(Duration::from_secs(5));
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["database started", "greeter started", "printed hello"]
+    );
+}
+
+#[test]
+fn test_events_are_dispatched_by_hand_without_waiting_for_the_barrier() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    let log = all_event_loops.printer.log.clone();
+
+    all_event_loops
+        .publisher
+        .publish_greeting(Greeting::new("hello".to_string()));
+    main_event_loop.dispatch_events();
+    all_event_loops.printer.process_events();
+
+    assert_eq!(*log.lock().unwrap(), vec!["printed hello"]);
+}
+
+pel::create_event_loops!(
+    system: SelfDependent
+    events: Unused {}
+    reactive loops: Alone {} starts after (Alone)
+);
+
+impl self_dependent::AloneEventHandlers for self_dependent::Alone {}
+
+pel::create_event_loops!(
+    system: Cyclic
+    events: Unused {}
+    reactive loops:
+        First {} starts after (Third),
+        Second {} starts after (First),
+        Third {} starts after (Second),
+        Independent {}
+);
+
+impl cyclic::FirstEventHandlers for cyclic::First {}
+impl cyclic::SecondEventHandlers for cyclic::Second {}
+impl cyclic::ThirdEventHandlers for cyclic::Third {}
+impl cyclic::IndependentEventHandlers for cyclic::Independent {}
+
+#[test]
+#[should_panic(expected = "Alone cannot start after itself")]
+fn test_loop_starting_after_itself_is_refused() {
+    self_dependent::pel_create_event_loops();
+}
+
+#[test]
+#[should_panic(
+    expected = "The loops cannot start after each other: First starts after Third starts after Second starts after First"
+)]
+fn test_loops_starting_after_each_other_are_refused() {
+    cyclic::pel_create_event_loops();
+}
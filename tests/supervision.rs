@@ -0,0 +1,139 @@
+use pel::PelExit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static N_RESTARTING_STARTS: AtomicUsize = AtomicUsize::new(0);
+static RESTARTING_JOBS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static IGNORING_JOBS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+pel::create_event_loops!(
+    events: Job {id: u32}
+
+    active loops:
+        Producer {} publishes (Job)
+
+    reactive loops:
+        Restarting {jobs: Vec<u32> = Vec::new()} subscribes to (Job) on panic Restart,
+
+        Ignoring {jobs: Vec<u32> = Vec::new()} subscribes to (Job) on panic Ignore,
+
+        Limited {} subscribes to (Job) on panic Restart(1 per 1m)
+);
+
+impl MainLoop for Producer {
+    fn main_loop(&mut self) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    fn on_start(&mut self) {
+        for id in 0..5 {
+            self.publish_job(Job::new(id));
+        }
+    }
+}
+
+impl RestartingEventHandlers for Restarting {
+    fn on_start(&mut self) {
+        N_RESTARTING_STARTS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_job(&mut self, event: Job) {
+        assert_ne!(event.id, 2, "job 2 failed");
+        self.jobs.push(event.id);
+    }
+
+    fn on_shutdown(&mut self) {
+        *RESTARTING_JOBS.lock().unwrap() = self.jobs.clone();
+    }
+}
+
+impl IgnoringEventHandlers for Ignoring {
+    fn on_job(&mut self, event: Job) {
+        assert_ne!(event.id, 1, "job 1 failed");
+        self.jobs.push(event.id);
+    }
+
+    fn on_shutdown(&mut self) {
+        *IGNORING_JOBS.lock().unwrap() = self.jobs.clone();
+    }
+}
+
+impl LimitedEventHandlers for Limited {
+    fn on_job(&mut self, event: Job) {
+        if event.id >= 3 {
+            panic!("job {} failed", event.id);
+        }
+    }
+}
+
+#[test]
+fn test_panics_are_handled_by_the_supervision_policy_of_the_loop() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let threads = pel_launch_event_loops_in_threads(all_event_loops);
+    let exit = pel_run_main_loop_indefinitely(main_event_loop);
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+
+    // Limited was restarted once, then escalated
+    assert_eq!(exit, PelExit::new(101, "Limited panicked: job 4 failed"));
+    // Restarting lost its state when it was restarted
+    assert_eq!(N_RESTARTING_STARTS.load(Ordering::Relaxed), 2);
+    assert_eq!(*RESTARTING_JOBS.lock().unwrap(), vec![3, 4]);
+    // Ignoring kept its state
+    assert_eq!(*IGNORING_JOBS.lock().unwrap(), vec![0, 2, 3, 4]);
+}
+
+static N_FRAGILE_STARTS: AtomicUsize = AtomicUsize::new(0);
+
+pel::create_event_loops!(
+    system: Stopping
+    events: Task {}
+
+    reactive loops:
+        Fragile {} publishes (Task) subscribes to (Task) on panic Restart,
+
+        Escalating {} subscribes to (Task)
+);
+
+impl stopping::FragileEventHandlers for stopping::Fragile {
+    fn on_start(&mut self) {
+        N_FRAGILE_STARTS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_task(&mut self, _event: stopping::Task) {}
+
+    fn on_shutdown(&mut self) {
+        panic!("the shutdown failed");
+    }
+}
+
+impl stopping::EscalatingEventHandlers for stopping::Escalating {
+    fn on_task(&mut self, _event: stopping::Task) {
+        panic!("the task failed");
+    }
+}
+
+#[test]
+fn test_loops_are_stopped_when_their_panic_escalates_or_when_they_shut_down() {
+    let (main_event_loop, mut all_event_loops) = stopping::pel_create_event_loops();
+    all_event_loops.fragile.start();
+    all_event_loops.escalating.start();
+
+    // The panic escalates: the loop stops
+    all_event_loops.fragile.publish_task(stopping::Task::new());
+    main_event_loop.dispatch_events();
+    all_event_loops
+        .escalating
+        .supervise(|event_loop| event_loop.process_events());
+    assert!(!all_event_loops.escalating.is_running());
+
+    // The escalation shuts Fragile down, which panics while shutting down: it is not restarted
+    main_event_loop.dispatch_events();
+    while all_event_loops.fragile.is_running() {
+        all_event_loops
+            .fragile
+            .supervise(|event_loop| event_loop.process_events());
+    }
+    assert_eq!(N_FRAGILE_STARTS.load(Ordering::Relaxed), 1);
+}
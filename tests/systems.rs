@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+static REQUESTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static JOBS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+// Both systems declare an event loop named Receiver
+pel::create_event_loops!(
+    system: Gateway
+
+    events: Request {path: String}
+
+    active loops:
+        Listener {is_done: bool = false} publishes (Request)
+
+    reactive loops:
+        Receiver {} subscribes to (Request)
+);
+
+pel::create_event_loops!(
+    system: Worker
+
+    events: Job {id: u32}
+
+    active loops:
+        Listener {is_done: bool = false} publishes (Job)
+
+    reactive loops:
+        Receiver {} subscribes to (Job)
+);
+
+impl gateway::MainLoop for gateway::Listener {
+    fn main_loop(&mut self) {
+        if !self.is_done {
+            self.is_done = true;
+            self.publish_request(gateway::Request::new("/status".to_string()));
+            let _ = self.exit();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+impl gateway::ReceiverEventHandlers for gateway::Receiver {
+    fn on_request(&mut self, event: gateway::Request) {
+        REQUESTS.lock().unwrap().push(event.path);
+    }
+}
+
+impl worker::MainLoop for worker::Listener {
+    fn main_loop(&mut self) {
+        if !self.is_done {
+            self.is_done = true;
+            self.publish_job(worker::Job::new(7));
+            let _ = self.exit_with(3, "no more jobs");
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+impl worker::ReceiverEventHandlers for worker::Receiver {
+    fn on_job(&mut self, event: worker::Job) {
+        JOBS.lock().unwrap().push(event.id);
+    }
+}
+
+#[test]
+fn test_systems_are_launched_independently() {
+    let (gateway_main_event_loop, gateway_event_loops) = gateway::pel_create_event_loops();
+    let (worker_main_event_loop, worker_event_loops) = worker::pel_create_event_loops();
+
+    let gateway_threads = gateway::pel_launch_event_loops_in_threads(gateway_event_loops);
+    let gateway_exit = gateway::pel_run_main_loop_indefinitely(gateway_main_event_loop);
+    assert_eq!(gateway_threads.join(Duration::from_secs(5)), 0);
+    assert_eq!(gateway_exit.code, 0);
+    assert!(JOBS.lock().unwrap().is_empty());
+
+    let worker_threads = worker::pel_launch_event_loops_in_threads(worker_event_loops);
+    let worker_exit = worker::pel_run_main_loop_indefinitely(worker_main_event_loop);
+    assert_eq!(worker_threads.join(Duration::from_secs(5)), 0);
+    assert_eq!(worker_exit.code, 3);
+
+    assert_eq!(*REQUESTS.lock().unwrap(), vec!["/status"]);
+    assert_eq!(*JOBS.lock().unwrap(), vec![7]);
+}
@@ -0,0 +1,111 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+static THREAD_NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+#[cfg(target_os = "linux")]
+static PINNED_THREAD_STATUS: Mutex<Option<(String, i32)>> = Mutex::new(None);
+
+pel::create_event_loops!(
+    events: Tick {}
+
+    active loops:
+        Clock {} publishes (Tick)
+
+    reactive loops:
+        Default {} subscribes to (Tick),
+
+        Pinned {} subscribes to (Tick)
+            thread named "pinned-loop" stack 262144 pinned to (0) priority Nice(5)
+);
+
+impl MainLoop for Clock {
+    fn main_loop(&mut self) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    fn on_start(&mut self) {
+        record_thread_name();
+        let _ = self.exit();
+    }
+}
+
+impl DefaultEventHandlers for Default {
+    fn on_start(&mut self) {
+        record_thread_name();
+    }
+
+    fn on_tick(&mut self, _event: Tick) {}
+}
+
+impl PinnedEventHandlers for Pinned {
+    fn on_start(&mut self) {
+        record_thread_name();
+        #[cfg(target_os = "linux")]
+        {
+            *PINNED_THREAD_STATUS.lock().unwrap() = Some((allowed_cpus(), nice_value()));
+        }
+    }
+
+    fn on_tick(&mut self, _event: Tick) {}
+}
+
+fn record_thread_name() {
+    let name = std::thread::current()
+        .name()
+        .unwrap_or("unnamed")
+        .to_string();
+    THREAD_NAMES.lock().unwrap().push(name);
+}
+
+#[cfg(target_os = "linux")]
+fn allowed_cpus() -> String {
+    let status = std::fs::read_to_string("/proc/thread-self/status").unwrap();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+// Ranges of CPUs such as 0-3,8
+#[cfg(target_os = "linux")]
+fn is_cpu_0_allowed(allowed_cpus: &str) -> bool {
+    allowed_cpus
+        .split(',')
+        .any(|range| range.split('-').next() == Some("0"))
+}
+
+#[cfg(target_os = "linux")]
+fn nice_value() -> i32 {
+    let stat = std::fs::read_to_string("/proc/thread-self/stat").unwrap();
+    // The fields after the command name, which is in parentheses, start at the state
+    let fields = stat[stat.rfind(')').unwrap() + 1..]
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    fields[16].parse().unwrap()
+}
+
+#[test]
+fn test_threads_are_configured_per_loop() {
+    #[cfg(target_os = "linux")]
+    let can_pin_to_cpu_0 = is_cpu_0_allowed(&allowed_cpus());
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let threads = pel_launch_event_loops_in_threads(all_event_loops);
+    pel_run_main_loop_indefinitely(main_event_loop);
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+
+    let mut names = THREAD_NAMES.lock().unwrap().clone();
+    names.sort();
+    assert_eq!(names, vec!["Clock", "Default", "pinned-loop"]);
+    #[cfg(target_os = "linux")]
+    {
+        let (pinned_cpus, nice) = PINNED_THREAD_STATUS.lock().unwrap().clone().unwrap();
+        // Pinning to a CPU the process may not use fails, and is only logged
+        if can_pin_to_cpu_0 {
+            assert_eq!(pinned_cpus, "0");
+        }
+        assert_eq!(nice, 5);
+    }
+}
@@ -0,0 +1,116 @@
+use pel::PelTestCondvar;
+use std::sync::{Arc, Mutex};
+
+pel::create_event_loops!(
+    events: Job {id: u32}
+
+    active loops:
+        Publisher
+            {cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new())}
+            publishes (Job),
+
+        RoundRobinWorker {jobs: Vec<u32> = Vec::new()}
+            subscribes to (Job) instances 3,
+
+        LeastQueuedWorker {jobs: Vec<u32> = Vec::new()}
+            subscribes to (Job) instances 2 balanced by LeastQueued
+
+    reactive loops:
+        ThreadedWorker
+            {
+                cvar: Arc<PelTestCondvar> = Arc::new(PelTestCondvar::new()),
+                jobs: Arc<Mutex<Vec<u32>>> = Arc::new(Mutex::new(Vec::new()))
+            }
+            subscribes to (Job) instances 4
+);
+
+impl MainLoop for Publisher {
+    fn main_loop(&mut self) {
+        self.cvar.wait();
+        for id in 0..8 {
+            self.publish_job(Job::new(id));
+        }
+    }
+}
+
+impl MainLoop for RoundRobinWorker {
+    fn main_loop(&mut self) {}
+}
+
+impl MainLoop for LeastQueuedWorker {
+    fn main_loop(&mut self) {}
+}
+
+impl RoundRobinWorkerEventHandlers for RoundRobinWorker {
+    fn on_job(&mut self, event: Job) {
+        self.jobs.push(event.id);
+    }
+}
+
+impl LeastQueuedWorkerEventHandlers for LeastQueuedWorker {
+    fn on_job(&mut self, event: Job) {
+        self.jobs.push(event.id);
+    }
+}
+
+impl ThreadedWorkerEventHandlers for ThreadedWorker {
+    fn on_job(&mut self, event: Job) {
+        self.jobs.lock().unwrap().push(event.id);
+        self.cvar.notify();
+    }
+}
+
+#[test]
+fn test_events_are_spread_over_instances() {
+    let (main_event_loop, mut all_event_loops) = pel_create_event_loops();
+    assert_eq!(all_event_loops.round_robin_worker.len(), 3);
+    assert_eq!(all_event_loops.least_queued_worker.len(), 2);
+    assert_eq!(all_event_loops.threaded_worker.len(), 4);
+
+    for id in 0..6 {
+        all_event_loops.publisher.publish_job(Job::new(id));
+        main_event_loop.dispatch_events();
+    }
+
+    for worker in all_event_loops.round_robin_worker.iter_mut() {
+        worker.process_events();
+    }
+    let round_robin_jobs = all_event_loops
+        .round_robin_worker
+        .iter()
+        .map(|worker| worker.jobs.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(round_robin_jobs, vec![vec![0, 3], vec![1, 4], vec![2, 5]]);
+
+    // Nothing was processed: the instances take turns as the one with the shortest queue
+    for worker in all_event_loops.least_queued_worker.iter_mut() {
+        worker.process_events();
+        assert_eq!(worker.jobs.len(), 3);
+    }
+}
+
+#[test]
+fn test_instances_run_in_their_own_thread() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+    let publisher_cvar = all_event_loops.publisher.cvar.clone();
+    let workers = all_event_loops
+        .threaded_worker
+        .iter()
+        .map(|worker| (worker.cvar.clone(), worker.jobs.clone()))
+        .collect::<Vec<_>>();
+
+    pel_launch_event_loops_in_threads(all_event_loops);
+    std::thread::spawn(move || pel_run_main_loop_indefinitely(main_event_loop));
+
+    publisher_cvar.notify();
+    let mut all_jobs = Vec::new();
+    for (cvar, jobs) in workers {
+        // Each worker gets two jobs
+        while jobs.lock().unwrap().len() < 2 {
+            cvar.wait();
+        }
+        all_jobs.extend(jobs.lock().unwrap().iter().copied());
+    }
+    all_jobs.sort_unstable();
+    assert_eq!(all_jobs, (0..8).collect::<Vec<_>>());
+}
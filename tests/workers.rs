@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const N_PINGS: usize = 200;
+
+static N_PINGS_RECEIVED: AtomicUsize = AtomicUsize::new(0);
+static N_TICKS: AtomicUsize = AtomicUsize::new(0);
+static WORKER_THREADS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+pel::create_event_loops!(
+    events: Ping {}
+
+    active loops:
+        Pinger {} publishes (Ping)
+
+    reactive loops:
+        Ponger {n_pings: usize = 0} subscribes to (Ping) starts after (Clock) instances 20,
+
+        Clock {} every 5ms => on_tick
+
+    startup: Barrier
+);
+
+impl MainLoop for Pinger {
+    fn main_loop(&mut self) {
+        if N_PINGS_RECEIVED.load(Ordering::Relaxed) == N_PINGS
+            && N_TICKS.load(Ordering::Relaxed) >= 3
+        {
+            let _ = self.exit();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    fn on_start(&mut self) {
+        for _ in 0..N_PINGS {
+            self.publish_ping(Ping::new());
+        }
+    }
+}
+
+impl PongerEventHandlers for Ponger {
+    fn on_ping(&mut self, _event: Ping) {
+        self.n_pings += 1;
+        N_PINGS_RECEIVED.fetch_add(1, Ordering::Relaxed);
+        record_worker_thread();
+    }
+}
+
+impl ClockEventHandlers for Clock {
+    fn on_tick(&mut self) {
+        N_TICKS.fetch_add(1, Ordering::Relaxed);
+        record_worker_thread();
+    }
+}
+
+fn record_worker_thread() {
+    let name = std::thread::current()
+        .name()
+        .unwrap_or("unnamed")
+        .to_string();
+    WORKER_THREADS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(name);
+}
+
+#[test]
+fn test_reactive_loops_share_the_workers() {
+    let (main_event_loop, all_event_loops) = pel_create_event_loops();
+
+    let threads = pel_launch_event_loops_in_workers(all_event_loops, 2);
+    let exit = pel_run_main_loop_indefinitely(main_event_loop);
+    assert_eq!(threads.join(Duration::from_secs(5)), 0);
+
+    assert_eq!(exit.code, 0);
+    assert_eq!(N_PINGS_RECEIVED.load(Ordering::Relaxed), N_PINGS);
+    let worker_threads = WORKER_THREADS.lock().unwrap().take().unwrap();
+    assert!(worker_threads
+        .iter()
+        .all(|name| name == "pel-worker-0" || name == "pel-worker-1"));
+}